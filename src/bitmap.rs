@@ -0,0 +1,168 @@
+//! 256-bit bitmaps of interrupt vectors.
+
+use crate::*;
+
+/// 256-bit vector bitmap for the [`ISR`](LocalApic::in_service),
+/// [`TMR`](LocalApic::trigger_mode) or
+/// [`IRR`](LocalApic::interrupt_request), where bit N corresponds to
+/// interrupt vector N.
+///
+/// Each of the 8 dwords that make up the bitmap lives in its own
+/// 16-byte [`BitfieldRegister`], so the bitmap can be indexed to
+/// access them individually.
+#[repr(transparent)]
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bytemuck", derive(Pod, Zeroable))]
+#[cfg_attr(
+	feature = "zerocopy",
+	derive(FromZeroes, FromBytes, AsBytes)
+)]
+pub struct Bitmap256(pub [BitfieldRegister; 8]);
+
+impl Bitmap256 {
+	/// Returns whether the bit for the given interrupt vector is set.
+	#[inline]
+	pub fn is_vector_set(&self, vector: u8) -> bool {
+		self.0[usize::from(vector / 32)].get_bit(vector % 32)
+	}
+
+	/// Returns an iterator over the interrupt vectors whose bit is
+	/// set, in ascending order.
+	pub fn set_vectors(&self) -> impl Iterator<Item = u8> + '_ {
+		(0..=u8::MAX).filter(|v| self.is_vector_set(*v))
+	}
+
+	/// Returns the highest interrupt vector whose bit is set, or
+	/// [`None`] if the bitmap is empty.
+	pub fn highest_set_vector(&self) -> Option<u8> {
+		self.0.iter().enumerate().rev().find_map(|(i, reg)| {
+			let bits = reg.bitfield();
+			if bits == 0 {
+				return None;
+			}
+			Some((i as u8) * 32 + (31 - bits.leading_zeros() as u8))
+		})
+	}
+
+	/// Sets the bit for the given interrupt vector.
+	#[inline]
+	pub fn set_vector(&mut self, vector: u8) {
+		self.0[usize::from(vector / 32)].set_bit(vector % 32, true);
+	}
+
+	/// Clears the bit for the given interrupt vector.
+	#[inline]
+	pub fn clear_vector(&mut self, vector: u8) {
+		self.0[usize::from(vector / 32)].set_bit(vector % 32, false);
+	}
+
+	/// Clears the bits of all the interrupt vectors. Only the first
+	/// dword of each register is modified, so the reserved bits of
+	/// each slot are left intact.
+	#[inline]
+	pub fn clear(&mut self) {
+		for reg in self.0.iter_mut() {
+			reg.set_bitfield(0);
+		}
+	}
+
+	/// Returns the number of interrupt vectors whose bit is set.
+	pub fn count_ones(&self) -> u32 {
+		self.0.iter().map(|reg| reg.bitfield().count_ones()).sum()
+	}
+}
+
+impl Index<usize> for Bitmap256 {
+	type Output = BitfieldRegister;
+
+	#[inline]
+	fn index(&self, index: usize) -> &Self::Output {
+		&self.0[index]
+	}
+}
+
+impl IndexMut<usize> for Bitmap256 {
+	#[inline]
+	fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+		&mut self.0[index]
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn bitmap_vectors() {
+		let mut isr = Bitmap256::default();
+		isr[6].set_bitfield(1 << 8);
+		assert!(isr.is_vector_set(200));
+		assert!(!isr.is_vector_set(199));
+		assert!(!isr.is_vector_set(8));
+
+		isr[0].set_bitfield(1 << 31);
+		let mut vectors = isr.set_vectors();
+		assert_eq!(vectors.next(), Some(31));
+		assert_eq!(vectors.next(), Some(200));
+		assert_eq!(vectors.next(), None);
+	}
+
+	#[test]
+	fn bitmap_highest_vector() {
+		let mut irr = Bitmap256::default();
+		assert_eq!(irr.highest_set_vector(), None);
+
+		for vector in [15, 100, 240] {
+			let reg = &mut irr[vector / 32];
+			reg.set_bitfield(reg.bitfield() | 1 << (vector % 32));
+		}
+		assert_eq!(irr.highest_set_vector(), Some(240));
+	}
+
+	#[test]
+	fn set_clear_vector() {
+		let mut bitmap = Bitmap256::default();
+		for vector in [0, 31, 32, 0x80, 255] {
+			bitmap.set_vector(vector);
+			assert!(bitmap.is_vector_set(vector));
+		}
+		assert_eq!(bitmap[1].bitfield(), 1);
+		assert_eq!(bitmap.highest_set_vector(), Some(255));
+
+		bitmap.clear_vector(255);
+		bitmap.clear_vector(254);
+		assert!(!bitmap.is_vector_set(255));
+		assert_eq!(bitmap.highest_set_vector(), Some(0x80));
+		assert!(bitmap.set_vectors().eq([0, 31, 32, 0x80]));
+	}
+
+	#[test]
+	fn count_ones() {
+		let mut bitmap = Bitmap256::default();
+		assert_eq!(bitmap.count_ones(), 0);
+		for vector in [0x20, 0x21, 0xff] {
+			bitmap.set_vector(vector);
+		}
+		assert_eq!(bitmap.count_ones(), 3);
+	}
+
+	#[test]
+	fn clear() {
+		let mut bitmap = Bitmap256::default();
+		for vector in [0x10, 0x50, 0xff] {
+			bitmap.set_vector(vector);
+		}
+		bitmap.clear();
+		assert_eq!(bitmap.count_ones(), 0);
+		assert_eq!(bitmap, Bitmap256::default());
+
+		let mut bytes = [0; 16];
+		bytes[0] = 1;
+		bytes[15] = 0xaa;
+		bitmap[3] = BitfieldRegister::from_bytes(bytes);
+		bitmap.clear();
+		assert_eq!(bitmap.count_ones(), 0);
+		assert_eq!(bitmap[3].into_bytes()[15], 0xaa);
+	}
+}
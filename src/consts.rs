@@ -0,0 +1,41 @@
+//! Well-known local APIC addresses and values.
+
+/// Default physical base address of the local APIC registers after
+/// reset, as reported by [`ApicBase`](crate::ApicBase).
+pub const DEFAULT_BASE_ADDR: u64 = 0xfee0_0000;
+
+/// Size in bytes of the memory-mapped local APIC register page.
+pub const MMIO_SIZE: usize = 0x1000;
+
+/// Address of the `IA32_APIC_BASE` MSR.
+pub const IA32_APIC_BASE_MSR: u32 = 0x1b;
+
+/// Address of the `IA32_TSC_DEADLINE` MSR.
+pub const IA32_TSC_DEADLINE_MSR: u32 = 0x6e0;
+
+/// First MSR address of the local APIC registers in x2APIC mode.
+/// Each register is located at this base plus its xAPIC offset
+/// divided by 16.
+pub const X2APIC_MSR_BASE: u32 = 0x800;
+
+/// Value of the spurious vector after reset.
+pub const SPURIOUS_VECTOR_RESET: u8 = 0xff;
+
+/// Lowest interrupt vector that can be used; vectors 0-15 are
+/// reserved.
+pub const MIN_VECTOR: u8 = 16;
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::*;
+
+	#[test]
+	fn x2apic_msrs() {
+		let offset = ApicRegisterOffset::SpuriousInterruptVector;
+		assert_eq!(
+			X2APIC_MSR_BASE + u32::from(offset.offset() >> 4),
+			X2ApicRegister::SpuriousInterruptVector.msr()
+		);
+	}
+}
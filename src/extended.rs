@@ -0,0 +1,212 @@
+//! Support for the AMD extended APIC registers.
+//!
+//! AMD processors that report the `ExtApicSpace` CPUID feature
+//! (`CPUID Fn8000_0001_ECX[3]`) implement additional registers
+//! starting at offset `0x400` of the local APIC register page. See
+//! chapter 16 of the AMD Architecture Programmer's Manual Vol. 2
+//! for details.
+
+use crate::*;
+
+/// Local APIC registers, including the AMD extended APIC register
+/// block at offsets `0x400`-`0x53F`.
+#[repr(C, align(16))]
+#[derive(Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bytemuck", derive(Pod, Zeroable))]
+#[cfg_attr(
+	feature = "zerocopy",
+	derive(FromZeroes, FromBytes, AsBytes)
+)]
+pub struct ExtendedLocalApic {
+	/// Standard local APIC registers.
+	pub apic: LocalApic,
+	/// Extended APIC Feature Register.
+	pub ext_features: ExtApicFeature,
+	/// Extended APIC Control Register.
+	pub ext_control: ExtApicControl,
+	/// Specific End of Interrupt Register (SEOI).
+	pub specific_eoi: SpecificEoi,
+	pub(crate) __reserved1: [Reserved; 5],
+	/// Interrupt Enable Registers (IER).
+	pub interrupt_enable: Bitmap256,
+	/// Extended Interrupt Local Vector Table Entries.
+	pub ext_lvt: [ExtendedLVT; 4],
+}
+
+// Layout checks at build time, as done for `LocalApic`.
+const _: () = assert!(mem::size_of::<ExtendedLocalApic>() == 0x540);
+const _: () = assert!(
+	mem::offset_of!(ExtendedLocalApic, ext_features) == 0x400
+);
+const _: () =
+	assert!(mem::offset_of!(ExtendedLocalApic, ext_control) == 0x410);
+const _: () = assert!(
+	mem::offset_of!(ExtendedLocalApic, specific_eoi) == 0x420
+);
+const _: () = assert!(
+	mem::offset_of!(ExtendedLocalApic, interrupt_enable) == 0x480
+);
+const _: () =
+	assert!(mem::offset_of!(ExtendedLocalApic, ext_lvt) == 0x500);
+
+impl fmt::Debug for ExtendedLocalApic {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("ExtendedLocalApic")
+			.field("apic", &self.apic)
+			.field("ext_features", &self.ext_features)
+			.field("ext_control", &self.ext_control)
+			.field("specific_eoi", &self.specific_eoi)
+			.field("interrupt_enable", &self.interrupt_enable)
+			.field("ext_lvt", &self.ext_lvt)
+			.finish()
+	}
+}
+
+/// Extended APIC Feature Register.
+#[bitfield(bits = 128)]
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bytemuck", derive(Pod, Zeroable))]
+#[cfg_attr(
+	feature = "zerocopy",
+	derive(FromZeroes, FromBytes, AsBytes)
+)]
+#[repr(transparent)]
+pub struct ExtApicFeature {
+	/// Whether the Interrupt Enable Registers are implemented.
+	pub ier_capable: B1,
+	/// Whether the Specific EOI Register is implemented.
+	pub seoi_capable: B1,
+	/// Whether 8-bit extended APIC IDs are supported.
+	pub ext_apic_id_capable: B1,
+	#[skip]
+	__: B13,
+	/// Number of Extended Interrupt LVT entries.
+	pub ext_lvt_count: u8,
+	#[skip]
+	__: B8,
+	#[skip]
+	__: B96,
+}
+
+/// Extended APIC Control Register.
+#[bitfield(bits = 128)]
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bytemuck", derive(Pod, Zeroable))]
+#[cfg_attr(
+	feature = "zerocopy",
+	derive(FromZeroes, FromBytes, AsBytes)
+)]
+#[repr(transparent)]
+pub struct ExtApicControl {
+	/// Enables the Interrupt Enable Registers.
+	pub ier_enable: B1,
+	/// Enables the Specific EOI Register.
+	pub seoi_enable: B1,
+	/// Enables 8-bit extended APIC IDs.
+	pub ext_apic_id_enable: B1,
+	#[skip]
+	__: B29,
+	#[skip]
+	__: B96,
+}
+
+/// Specific End of Interrupt Register (SEOI).
+#[bitfield(bits = 128)]
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bytemuck", derive(Pod, Zeroable))]
+#[cfg_attr(
+	feature = "zerocopy",
+	derive(FromZeroes, FromBytes, AsBytes)
+)]
+#[repr(transparent)]
+pub struct SpecificEoi {
+	/// Vector of the interrupt to signal the end of.
+	pub vector: u8,
+	#[skip]
+	__: B24,
+	#[skip]
+	__: B96,
+}
+
+/// Extended Interrupt Local Vector Table Entry.
+#[bitfield(bits = 128)]
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bytemuck", derive(Pod, Zeroable))]
+#[cfg_attr(
+	feature = "zerocopy",
+	derive(FromZeroes, FromBytes, AsBytes)
+)]
+#[repr(transparent)]
+pub struct ExtendedLVT {
+	pub vector: u8,
+	#[bits = 3]
+	pub delivery_mode: DeliveryMode,
+	#[skip]
+	__: B1,
+	/// Whether the interrupt is still pending delivery. This bit is
+	/// read-only, so no setter is provided.
+	#[bits = 1]
+	#[skip(setters)]
+	pub delivery_status: DeliveryStatus,
+	#[skip]
+	__: B3,
+	pub mask: B1,
+	#[skip]
+	__: B15,
+	#[skip]
+	__: B96,
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use core::mem;
+	use memoffset::offset_of;
+
+	#[test]
+	fn test_offsets() {
+		// Offsets from Table 16-2. APIC Registers - AMD Architecture
+		// Programmer's Manual Vol. 2: System Programming
+		assert_eq!(offset_of!(ExtendedLocalApic, apic), 0x0);
+		assert_eq!(
+			offset_of!(ExtendedLocalApic, ext_features),
+			0x400
+		);
+		assert_eq!(offset_of!(ExtendedLocalApic, ext_control), 0x410);
+		assert_eq!(
+			offset_of!(ExtendedLocalApic, specific_eoi),
+			0x420
+		);
+		assert_eq!(
+			offset_of!(ExtendedLocalApic, interrupt_enable),
+			0x480
+		);
+		assert_eq!(offset_of!(ExtendedLocalApic, ext_lvt), 0x500);
+		assert_eq!(
+			offset_of!(ExtendedLocalApic, ext_lvt)
+				+ 3 * mem::size_of::<ExtendedLVT>(),
+			0x530
+		);
+		assert_eq!(mem::size_of::<ExtendedLocalApic>(), 0x540);
+	}
+
+	#[test]
+	fn ext_features() {
+		let feat = ExtApicFeature::from_u32(0x0004_0007);
+		assert_eq!(feat.ier_capable(), 1);
+		assert_eq!(feat.seoi_capable(), 1);
+		assert_eq!(feat.ext_apic_id_capable(), 1);
+		assert_eq!(feat.ext_lvt_count(), 4);
+
+		let lvt = ExtendedLVT::new()
+			.with_vector(0x50)
+			.with_delivery_mode(DeliveryMode::Nmi)
+			.with_mask(1);
+		assert_eq!(bytes_to_dword(lvt.into_bytes()), 0x10450);
+	}
+}
@@ -0,0 +1,187 @@
+//! Implementations of [`arbitrary::Arbitrary`] for the registers.
+//!
+//! Generated registers have their reserved upper dwords cleared, and
+//! fields with reserved encodings, like [`DeliveryMode::Reserved3`]
+//! or [`TimerMode::Reserved`], are always set to a defined value, so
+//! that fuzzers explore states that a real local APIC could be in.
+
+use crate::*;
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+/// Implements [`Arbitrary`] for an enum by choosing one of the given
+/// variants.
+macro_rules! impl_arbitrary_enum {
+	($($ty:ident [$($variant:ident),+ $(,)?])+) => {
+		$(
+			impl<'a> Arbitrary<'a> for $ty {
+				fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+					u.choose(&[$(Self::$variant),+]).copied()
+				}
+			}
+		)+
+	};
+}
+
+impl_arbitrary_enum! {
+	DeliveryMode [
+		Fixed,
+		LowestPriority,
+		Smi,
+		Nmi,
+		Init,
+		Startup,
+		ExtInt,
+	]
+	DeliveryStatus [Idle, SendPending]
+	DestinationShorthand [
+		NoShorthand,
+		SelfOnly,
+		AllIncludingSelf,
+		AllExcludingSelf,
+	]
+	DestinationMode [Physical, Logical]
+	DestinationModel [Cluster, Flat]
+	TriggerMode [Edge, Level]
+	PinPolarity [ActiveHigh, ActiveLow]
+	Level [Deassert, Assert]
+	TimerMode [OneShot, Periodic, TscDeadline]
+	TimerDivisor [Div1, Div2, Div4, Div8, Div16, Div32, Div64, Div128]
+}
+
+/// Implements [`Arbitrary`] for a register from an arbitrary first
+/// dword, then overwriting the given fields through their setters
+/// with arbitrary values of their types.
+macro_rules! impl_arbitrary_register {
+	($($reg:ident { $($set:ident),* })+) => {
+		$(
+			impl<'a> Arbitrary<'a> for $reg {
+				fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+					#[allow(unused_mut)]
+					let mut reg = Self::from_u32(u.arbitrary()?);
+					$(reg.$set(u.arbitrary()?);)*
+					Ok(reg)
+				}
+			}
+		)+
+	};
+}
+
+impl_arbitrary_register! {
+	ApicId {}
+	ApicVersion {}
+	PriorityRegister {}
+	EndOfInterrupt {}
+	LogicalDestination {}
+	DestinationFormat { set_destination_model }
+	SpuriousInterruptVector {}
+	BitfieldRegister {}
+	ErrorStatus {}
+	CmciLVT { set_delivery_mode }
+	InterruptCmdLow { set_delivery_mode }
+	InterruptCmdHigh {}
+	TimerLVT { set_timer_mode }
+	ThermalLVT { set_delivery_mode }
+	PerfLVT { set_delivery_mode }
+	LIntLVT { set_delivery_mode }
+	ErrorLVT {}
+	TimerCount {}
+	TimerDivConf { set_divide_by }
+	ExtApicFeature {}
+	ExtApicControl {}
+	SpecificEoi {}
+	ExtendedLVT { set_delivery_mode }
+}
+
+impl<'a> Arbitrary<'a> for Bitmap256 {
+	fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+		Ok(Self(u.arbitrary()?))
+	}
+}
+
+/// Implements [`Arbitrary`] for a structure by generating each of the
+/// given fields, and leaving the rest set to their default value.
+macro_rules! impl_arbitrary_struct {
+	($ty:ident { $($field:ident),+ $(,)? }) => {
+		impl<'a> Arbitrary<'a> for $ty {
+			fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+				Ok(Self {
+					$($field: u.arbitrary()?,)+
+					..Default::default()
+				})
+			}
+		}
+	};
+}
+
+impl_arbitrary_struct!(LocalApic {
+	apic_id,
+	apic_version,
+	task_priority,
+	arb_priority,
+	processor_priority,
+	eoi,
+	logical_dst,
+	dst_format,
+	spurious_iv,
+	in_service,
+	trigger_mode,
+	interrupt_request,
+	error_status,
+	cmci_lvt,
+	interrupt_cmd_low,
+	interrupt_cmd_high,
+	timer_lvt,
+	thermal_lvt,
+	performance_lvt,
+	lint0_lvt,
+	lint1_lvt,
+	error_lvt,
+	timer_icr,
+	timer_ccr,
+	timer_dcr,
+});
+
+impl_arbitrary_struct!(ExtendedLocalApic {
+	apic,
+	ext_features,
+	ext_control,
+	specific_eoi,
+	interrupt_enable,
+	ext_lvt,
+});
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn smoke() {
+		let mut state = 0x2545_f491_4f6c_dd1du64;
+		let data: Vec<u8> = (0..0x4000)
+			.map(|_| {
+				state ^= state << 13;
+				state ^= state >> 7;
+				state ^= state << 17;
+				state as u8
+			})
+			.collect();
+
+		let mut u = Unstructured::new(&data);
+		for _ in 0..8 {
+			let apic = LocalApic::arbitrary(&mut u).unwrap();
+			assert_ne!(
+				apic.timer_lvt.timer_mode(),
+				TimerMode::Reserved
+			);
+			assert!(apic.dst_format.destination_model().is_some());
+			assert!(TimerDivisor::from_bits(
+				apic.timer_dcr.divisor()
+			)
+			.is_some());
+			for (_, lvt) in apic.lvt_entries() {
+				let _ = lvt.is_masked();
+			}
+		}
+		ExtendedLocalApic::arbitrary(&mut u).unwrap();
+	}
+}
@@ -1,9 +1,13 @@
 #![cfg_attr(not(test), no_std)]
-#![forbid(unsafe_code)]
+#![cfg_attr(not(feature = "volatile"), forbid(unsafe_code))]
+#![cfg_attr(feature = "volatile", deny(unsafe_code))]
 #![forbid(missing_docs)]
 #![forbid(missing_copy_implementations)]
 #![forbid(missing_debug_implementations)]
 #![cfg_attr(docsrs, feature(doc_auto_cfg))]
+// The code generated by `#[bitfield]` wraps field types in
+// parentheses, which newer compilers flag as unnecessary.
+#![allow(unused_parens)]
 
 //! A self-contained crate implementing safe types for the local APIC
 //! registers on x86_64 systems.
@@ -14,6 +18,9 @@
 //!
 //! This crate does not depend on the standard Rust library and uses
 //! [`#![forbid(unsafe_code)]`](https://doc.rust-lang.org/nomicon/safe-unsafe-meaning.html#how-safe-and-unsafe-interact).
+//! The only exception is the optional `volatile` feature, which
+//! enables the [`VolatileApic`] wrapper and the `mmio` functions for
+//! accessing memory-mapped registers.
 //!
 //! # Usage
 //!
@@ -27,14 +34,128 @@
 //! methods to convert the registers to and from raw bytes, thanks to
 //! the [modular_bitfield](https://docs.rs/modular-bitfield/latest/modular_bitfield/)
 //! crate.
+//!
+//! The following register constructors are `const`, so they can be
+//! used to build preconfigured registers in a `const` or `static`:
+//!
+//! * `new()`, which returns a zeroed register.
+//! * `from_bytes()`, which takes the raw 16 bytes of the register.
+//! * `from_u32()`, which takes the value of the first dword of the
+//!   register, where all its meaningful bits are located.
+//!
+//! ```
+//! # use lapic::TimerLVT;
+//! // Periodic timer (bit 17) with vector 0x40.
+//! static TIMER: TimerLVT = TimerLVT::from_u32(0x20040);
+//! assert_eq!(TIMER.vector(), 0x40);
+//! ```
+//!
+//! Next to the mutating `set_*` methods, which are best suited for
+//! hot paths, the registers provide consuming `with_*` variants of
+//! their setters and helpers, so that they can be configured in a
+//! single expression:
+//!
+//! ```
+//! # use lapic::{InvalidVector, MaskableLvt, SpuriousInterruptVector, TimerLVT};
+//! let lvt = TimerLVT::default().try_with_vector(0x40)?.masked();
+//! assert!(lvt.is_masked());
+//!
+//! let siv = SpuriousInterruptVector::new()
+//!     .with_spurious_vector(0xff)
+//!     .with_focus_processor_checking(false)
+//!     .enable();
+//! assert!(siv.is_enabled());
+//! # Ok::<(), InvalidVector>(())
+//! ```
+//!
+//! # Features
+//!
+//! * `alloc`: enables [`LocalApic::to_vec`], which returns the
+//!   memory image of the registers in an allocated buffer.
+//! * `arbitrary`: implements `arbitrary::Arbitrary` for
+//!   [`LocalApic`], [`ExtendedLocalApic`] and all their register
+//!   types, generating only defined encodings for their fields.
+//! * `bytemuck`: implements [`bytemuck::Pod`] and
+//!   [`bytemuck::Zeroable`] for [`LocalApic`] and all the register
+//!   types, allowing them to be safely cast to and from raw bytes.
+//! * `defmt`: implements `defmt::Format` for [`LocalApic`] and all
+//!   the register types, printing their decoded fields.
+//! * `serde`: implements `Serialize` and `Deserialize` for
+//!   [`LocalApic`] and all the register types. Registers are
+//!   serialized as their raw bytes, including reserved bits.
+//! * `volatile`: enables [`VolatileApic`] and the `mmio` module,
+//!   which perform volatile reads and writes on a memory-mapped
+//!   [`LocalApic`].
+//! * `zerocopy`: implements `zerocopy::FromZeroes`,
+//!   `zerocopy::FromBytes` and `zerocopy::AsBytes` for
+//!   [`LocalApic`] and all the register types.
+//!
+//! # Processor startup
+//!
+//! The [`smp`] module documents the INIT-SIPI-SIPI sequence used to
+//! start application processors, and provides the helpers to send
+//! each of its IPIs.
+//!
+//! # x2APIC
+//!
+//! The registers of a local APIC in x2APIC mode are accessed through
+//! MSRs instead of memory-mapped I/O. See [`X2Apic`] for details.
+//!
+//! # AMD extended APIC registers
+//!
+//! AMD processors may implement additional registers past the end
+//! of [`LocalApic`]. See [`ExtendedLocalApic`] for details.
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+mod bitmap;
+pub mod consts;
+mod extended;
+#[cfg(feature = "arbitrary")]
+mod fuzz;
+#[cfg(feature = "volatile")]
+pub mod mmio;
+mod offsets;
+pub mod smp;
+mod view;
+#[cfg(feature = "volatile")]
+mod volatile;
+mod x2apic;
 
+pub use bitmap::Bitmap256;
+#[cfg(feature = "bytemuck")]
+use bytemuck::{Pod, Zeroable};
 use core::fmt;
-use modular_bitfield::bitfield;
+use core::mem;
+use core::ops::{Index, IndexMut};
+pub use extended::{
+	ExtApicControl, ExtApicFeature, ExtendedLVT, ExtendedLocalApic,
+	SpecificEoi,
+};
 use modular_bitfield::specifiers::*;
+use modular_bitfield::{bitfield, BitfieldSpecifier, Specifier};
+pub use offsets::{ApicAccess, ApicRegister, ApicRegisterOffset};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+pub use view::LocalApicView;
+#[cfg(feature = "volatile")]
+pub use volatile::VolatileApic;
+pub use x2apic::{
+	MsrAccess, X2Apic, X2ApicIcr, X2ApicRegister, X2ApicSelfIpi,
+};
+#[cfg(feature = "zerocopy")]
+use zerocopy::{AsBytes, FromBytes, FromZeroes};
 
 /// Local APIC registers.
 #[repr(C, align(16))]
-#[derive(Copy, Clone, Default, PartialEq, Eq)]
+#[derive(Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bytemuck", derive(Pod, Zeroable))]
+#[cfg_attr(
+	feature = "zerocopy",
+	derive(FromZeroes, FromBytes, AsBytes)
+)]
 pub struct LocalApic {
 	__reserved1: [Reserved; 2],
 	/// APIC ID Register.
@@ -58,14 +179,17 @@ pub struct LocalApic {
 	/// Spurious Interrupt Vector Register.
 	pub spurious_iv: SpuriousInterruptVector,
 	/// In-Service Register (ISR).
-	pub in_service: [BitfieldRegister; 8],
+	pub in_service: Bitmap256,
 	/// Trigger Mode Register (TMR).
-	pub trigger_mode: [BitfieldRegister; 8],
+	pub trigger_mode: Bitmap256,
 	/// Interrupt Request Register (IRR).
-	pub interrupt_request: [BitfieldRegister; 8],
+	pub interrupt_request: Bitmap256,
 	/// Error Status Register (ESR).
 	pub error_status: ErrorStatus,
-	__reserved8: [Reserved; 7],
+	__reserved8: [Reserved; 6],
+	/// Corrected Machine Check Interrupt (CMCI) Local Vector Table
+	/// Entry.
+	pub cmci_lvt: CmciLVT,
 	/// Interrupt Command Register Low (bits 31:0).
 	pub interrupt_cmd_low: InterruptCmdLow,
 	/// Interrupt Command Register High (bits 63:32).
@@ -92,6 +216,640 @@ pub struct LocalApic {
 	__reserved10: Reserved,
 }
 
+/// Asserts at compile time that each of the given fields is at the
+/// given offset of the structure.
+macro_rules! assert_offsets {
+	($ty:ty { $($field:ident: $off:expr),+ $(,)? }) => {
+		$(
+			const _: () = assert!(mem::offset_of!($ty, $field) == $off);
+		)+
+	};
+}
+
+// Offsets from Table 16-2. APIC Registers - AMD Architecture
+// Programmer's Manual Vol. 2: System Programming. These are checked
+// when building the crate, so that layout regressions cannot go
+// unnoticed outside of tests.
+const _: () = assert!(mem::size_of::<LocalApic>() == 0x400);
+assert_offsets!(LocalApic {
+	apic_id: 0x20,
+	apic_version: 0x30,
+	task_priority: 0x80,
+	arb_priority: 0x90,
+	processor_priority: 0xa0,
+	eoi: 0xb0,
+	logical_dst: 0xd0,
+	dst_format: 0xe0,
+	spurious_iv: 0xf0,
+	in_service: 0x100,
+	trigger_mode: 0x180,
+	interrupt_request: 0x200,
+	error_status: 0x280,
+	cmci_lvt: 0x2f0,
+	interrupt_cmd_low: 0x300,
+	interrupt_cmd_high: 0x310,
+	timer_lvt: 0x320,
+	thermal_lvt: 0x330,
+	performance_lvt: 0x340,
+	lint0_lvt: 0x350,
+	lint1_lvt: 0x360,
+	error_lvt: 0x370,
+	timer_icr: 0x380,
+	timer_ccr: 0x390,
+	timer_dcr: 0x3e0,
+});
+
+impl LocalApic {
+	/// Casts a pointer to the base of the local APIC register page
+	/// into a pointer to [`LocalApic`], checking that it is
+	/// properly aligned.
+	///
+	/// This function does not dereference the pointer, so the caller
+	/// remains responsible of ensuring that it points to valid,
+	/// mapped memory before accessing it.
+	pub fn from_base_ptr(
+		ptr: *mut u8,
+	) -> Result<*mut LocalApic, AlignmentError> {
+		let apic = ptr.cast::<LocalApic>();
+		if !apic.is_aligned() {
+			return Err(AlignmentError(ptr as usize));
+		}
+		Ok(apic)
+	}
+
+	/// Returns the registers in the state they are left in after a
+	/// reset, which differs from the all-zeros [`Default`]:
+	///
+	/// * All the Local Vector Table entries are masked.
+	/// * The [`Destination Format Register`](LocalApic::dst_format) is
+	///   all ones (flat model).
+	/// * The [`Spurious Interrupt Vector Register`](LocalApic::spurious_iv)
+	///   holds vector `0xFF`, with the APIC software-disabled.
+	///
+	/// The rest of the registers are zero. Registers whose reset
+	/// value is implementation-specific, like the APIC ID and
+	/// version, must be filled in by the caller.
+	pub fn reset_state() -> Self {
+		let masked = 1 << 16;
+		Self {
+			dst_format: DestinationFormat::from_u32(u32::MAX),
+			spurious_iv: SpuriousInterruptVector::from_u32(
+				u32::from(consts::SPURIOUS_VECTOR_RESET),
+			),
+			cmci_lvt: CmciLVT::from_u32(masked),
+			timer_lvt: TimerLVT::from_u32(masked),
+			thermal_lvt: ThermalLVT::from_u32(masked),
+			performance_lvt: PerfLVT::from_u32(masked),
+			lint0_lvt: LIntLVT::from_u32(masked),
+			lint1_lvt: LIntLVT::from_u32(masked),
+			error_lvt: ErrorLVT::from_u32(masked),
+			..Default::default()
+		}
+	}
+
+	/// Signals the end of the current interrupt by writing zero to
+	/// the [`EOI`](LocalApic::eoi) register.
+	///
+	/// This is typically the last step of an interrupt handler:
+	///
+	/// ```
+	/// # use lapic::LocalApic;
+	/// fn timer_handler(apic: &mut LocalApic) {
+	///     // ... service the interrupt ...
+	///     apic.signal_eoi();
+	/// }
+	/// # timer_handler(&mut LocalApic::default());
+	/// ```
+	#[inline]
+	pub fn signal_eoi(&mut self) {
+		self.eoi = EndOfInterrupt::default();
+	}
+
+	/// Sends an Inter-Processor Interrupt (IPI) with the given
+	/// destination, vector, delivery mode and destination shorthand.
+	///
+	/// The [`ICR` high register](LocalApic::interrupt_cmd_high) is
+	/// written first, since writing the
+	/// [`ICR` low register](LocalApic::interrupt_cmd_low) is what
+	/// triggers the delivery of the interrupt. Callers should poll
+	/// the delivery status field of the `ICR` afterwards to know when
+	/// the interrupt has been accepted.
+	pub fn send_ipi(
+		&mut self,
+		dst: u8,
+		vector: u8,
+		mode: DeliveryMode,
+		shorthand: DestinationShorthand,
+	) {
+		self.interrupt_cmd_high =
+			InterruptCmdHigh::new().with_dst(dst);
+		self.interrupt_cmd_low =
+			InterruptCmdLow::ipi(vector, mode, shorthand);
+	}
+
+	/// Returns the full 64-bit value of the Interrupt Command
+	/// Register, combining
+	/// [`interrupt_cmd_high`](LocalApic::interrupt_cmd_high) and
+	/// [`interrupt_cmd_low`](LocalApic::interrupt_cmd_low).
+	#[inline]
+	pub fn read_icr(&self) -> u64 {
+		let low = bytes_to_dword(self.interrupt_cmd_low.into_bytes());
+		let high =
+			bytes_to_dword(self.interrupt_cmd_high.into_bytes());
+		(u64::from(high) << 32) | u64::from(low)
+	}
+
+	/// Writes the full 64-bit value of the Interrupt Command
+	/// Register. The high half is written first, since writing the
+	/// low half is what triggers the delivery of the interrupt.
+	#[inline]
+	pub fn write_icr(&mut self, val: u64) {
+		self.interrupt_cmd_high =
+			InterruptCmdHigh::from_u32((val >> 32) as u32);
+		self.interrupt_cmd_low =
+			InterruptCmdLow::from_u32(val as u32);
+	}
+
+	/// Sends an IPI like [`send_ipi`](Self::send_ipi), but returns
+	/// an error instead if a non-zero destination is given along
+	/// with a destination shorthand, since the hardware ignores the
+	/// destination in that case.
+	pub fn try_send_ipi(
+		&mut self,
+		dst: u8,
+		vector: u8,
+		mode: DeliveryMode,
+		shorthand: DestinationShorthand,
+	) -> Result<(), IpiConfigError> {
+		if dst != 0 && shorthand != DestinationShorthand::NoShorthand
+		{
+			return Err(IpiConfigError::DestinationWithShorthand(
+				shorthand,
+			));
+		}
+		self.send_ipi(dst, vector, mode, shorthand);
+		Ok(())
+	}
+
+	/// Sends a fixed IPI with the given vector to the local processor,
+	/// by using the [`SelfOnly`](DestinationShorthand::SelfOnly)
+	/// destination shorthand in the `ICR`.
+	///
+	/// In x2APIC mode, [`X2Apic::self_ipi`] should be preferred, as
+	/// it only requires a write to the dedicated Self IPI Register.
+	#[inline]
+	pub fn send_self_ipi(&mut self, vector: u8) {
+		self.send_ipi(
+			0,
+			vector,
+			DeliveryMode::Fixed,
+			DestinationShorthand::SelfOnly,
+		);
+	}
+
+	/// Returns an iterator over all the Local Vector Table entries,
+	/// in register order, along with the kind of each entry.
+	///
+	/// ```
+	/// # use lapic::{LocalApic, LvtKind};
+	/// let apic = LocalApic::default();
+	/// for (kind, lvt) in apic.lvt_entries() {
+	///     println!("{kind:?}: vector {:#x}", lvt.vector());
+	/// }
+	/// ```
+	pub fn lvt_entries(
+		&self,
+	) -> impl Iterator<Item = (LvtKind, &dyn MaskableLvt)> {
+		[
+			(LvtKind::Cmci, &self.cmci_lvt as &dyn MaskableLvt),
+			(LvtKind::Timer, &self.timer_lvt),
+			(LvtKind::Thermal, &self.thermal_lvt),
+			(LvtKind::Performance, &self.performance_lvt),
+			(LvtKind::Lint0, &self.lint0_lvt),
+			(LvtKind::Lint1, &self.lint1_lvt),
+			(LvtKind::Error, &self.error_lvt),
+		]
+		.into_iter()
+	}
+
+	/// Returns mutable references to all the Local Vector Table
+	/// entries, in register order.
+	fn lvts_mut(&mut self) -> [&mut dyn MaskableLvt; 7] {
+		[
+			&mut self.cmci_lvt,
+			&mut self.timer_lvt,
+			&mut self.thermal_lvt,
+			&mut self.performance_lvt,
+			&mut self.lint0_lvt,
+			&mut self.lint1_lvt,
+			&mut self.error_lvt,
+		]
+	}
+
+	/// Masks all the Local Vector Table entries, e.g. before taking
+	/// the processor offline.
+	#[inline]
+	pub fn mask_all_lvts(&mut self) {
+		mask_all(self.lvts_mut());
+	}
+
+	/// Unmasks all the Local Vector Table entries.
+	#[inline]
+	pub fn unmask_all_lvts(&mut self) {
+		for lvt in self.lvts_mut() {
+			lvt.set_masked(false);
+		}
+	}
+
+	/// Performs a read-modify-write of the Local Vector Table entry
+	/// of the given kind: the current value is passed to `f`, and
+	/// the returned value is written back. Since the whole register
+	/// is carried through, reserved bits are left intact, unlike
+	/// when assigning a freshly constructed entry.
+	///
+	/// The delivery status bit is deliberately not checked: it is
+	/// read-only, and whether to wait for a pending delivery before
+	/// reprogramming the entry is left to the caller.
+	///
+	/// ```
+	/// # use lapic::{LocalApic, LvtKind, MaskableLvt, TimerLVT};
+	/// let mut apic = LocalApic::default();
+	/// apic.update_lvt(LvtKind::Timer, |lvt: TimerLVT| {
+	///     lvt.with_vector(0x40).masked()
+	/// })
+	/// .unwrap();
+	/// assert_eq!(apic.timer_lvt.vector(), 0x40);
+	/// ```
+	///
+	/// # Errors
+	///
+	/// Returns [`LvtKindMismatch`] if `T` is not the type of the
+	/// entry of the given kind, leaving the entry untouched.
+	pub fn update_lvt<T: LvtEntry>(
+		&mut self,
+		kind: LvtKind,
+		f: impl FnOnce(T) -> T,
+	) -> Result<(), LvtKindMismatch> {
+		let lvt =
+			T::entry_mut(self, kind).ok_or(LvtKindMismatch(kind))?;
+		*lvt = f(*lvt);
+		Ok(())
+	}
+
+	/// Returns an iterator over the name and first dword of every
+	/// register, in register order. The bitmap registers yield one
+	/// entry per dword, named with their index, e.g.
+	/// `"in_service[0]"`.
+	///
+	/// ```
+	/// # use lapic::LocalApic;
+	/// let apic = LocalApic::default();
+	/// for (name, val) in apic.named_registers() {
+	///     println!("{name}: {val:#010x}");
+	/// }
+	/// ```
+	pub fn named_registers(
+		&self,
+	) -> impl Iterator<Item = (&'static str, u32)> {
+		macro_rules! named {
+			(@acc [$($out:tt)*]) => {
+				[$($out)*]
+			};
+			(
+				@acc [$($out:tt)*]
+				$field:ident [$($i:literal)+] $(, $($rest:tt)*)?
+			) => {
+				named!(@acc [
+					$($out)*
+					$((
+						concat!(stringify!($field), "[", $i, "]"),
+						self.$field[$i].bitfield(),
+					),)+
+				] $($($rest)*)?)
+			};
+			(@acc [$($out:tt)*] $field:ident $(, $($rest:tt)*)?) => {
+				named!(@acc [
+					$($out)*
+					(
+						stringify!($field),
+						bytes_to_dword(self.$field.into_bytes()),
+					),
+				] $($($rest)*)?)
+			};
+			($($fields:tt)+) => {
+				named!(@acc [] $($fields)+).into_iter()
+			};
+		}
+		named!(
+			apic_id,
+			apic_version,
+			task_priority,
+			arb_priority,
+			processor_priority,
+			eoi,
+			logical_dst,
+			dst_format,
+			spurious_iv,
+			in_service[0 1 2 3 4 5 6 7],
+			trigger_mode[0 1 2 3 4 5 6 7],
+			interrupt_request[0 1 2 3 4 5 6 7],
+			error_status,
+			cmci_lvt,
+			interrupt_cmd_low,
+			interrupt_cmd_high,
+			timer_lvt,
+			thermal_lvt,
+			performance_lvt,
+			lint0_lvt,
+			lint1_lvt,
+			error_lvt,
+			timer_icr,
+			timer_ccr,
+			timer_dcr
+		)
+	}
+
+	/// Configures the APIC timer to fire a single interrupt with the
+	/// given vector once `initial_count` ticks of the divided clock
+	/// have elapsed.
+	///
+	/// The registers are written in the order required by the
+	/// hardware: the
+	/// [`Timer Divide Configuration Register`](LocalApic::timer_dcr)
+	/// first, then the [`Timer LVT`](LocalApic::timer_lvt), which is
+	/// left unmasked, and finally the
+	/// [`Timer Initial Count Register`](LocalApic::timer_icr).
+	/// Writing the initial count is what starts the countdown.
+	#[inline]
+	pub fn configure_timer_one_shot(
+		&mut self,
+		vector: u8,
+		divisor: TimerDivisor,
+		initial_count: u32,
+	) {
+		self.configure_timer(
+			TimerMode::OneShot,
+			vector,
+			divisor,
+			initial_count,
+		);
+	}
+
+	/// Configures the APIC timer to fire an interrupt with the given
+	/// vector every `initial_count` ticks of the divided clock.
+	///
+	/// The registers are written in the same order as in
+	/// [`configure_timer_one_shot`](Self::configure_timer_one_shot),
+	/// and writing the initial count starts the countdown.
+	#[inline]
+	pub fn configure_timer_periodic(
+		&mut self,
+		vector: u8,
+		divisor: TimerDivisor,
+		initial_count: u32,
+	) {
+		self.configure_timer(
+			TimerMode::Periodic,
+			vector,
+			divisor,
+			initial_count,
+		);
+	}
+
+	/// Configures the APIC timer in TSC-deadline mode with the given
+	/// vector. The timer is then armed by writing a [`TscDeadline`]
+	/// to its MSR, rather than by writing the
+	/// [`Timer Initial Count Register`](LocalApic::timer_icr), which
+	/// is ignored in this mode.
+	///
+	/// ```
+	/// # use lapic::{LocalApic, MsrAccess, TimerDivisor, TscDeadline};
+	/// # struct Msrs;
+	/// # impl MsrAccess for Msrs {
+	/// #     fn read_msr(&self, _: u32) -> u64 { 0 }
+	/// #     fn write_msr(&self, _: u32, _: u64) {}
+	/// # }
+	/// # let msrs = Msrs;
+	/// # let now = 0;
+	/// let mut apic = LocalApic::default();
+	///
+	/// // One-shot and periodic timers are armed through the timer ICR.
+	/// apic.configure_timer_one_shot(0x40, TimerDivisor::Div16, 1000);
+	/// assert!(!apic.uses_tsc_deadline());
+	///
+	/// // TSC-deadline timers are armed through the MSR instead.
+	/// apic.configure_timer_tsc_deadline(0x40);
+	/// assert!(apic.uses_tsc_deadline());
+	/// TscDeadline(now + 1000).write(&msrs);
+	/// ```
+	#[inline]
+	pub fn configure_timer_tsc_deadline(&mut self, vector: u8) {
+		self.timer_lvt = TimerLVT::new()
+			.with_vector(vector)
+			.with_timer_mode(TimerMode::TscDeadline);
+	}
+
+	/// Returns whether the timer is in TSC-deadline mode, in which
+	/// case it must be armed by writing a [`TscDeadline`] to its MSR,
+	/// and the [`Timer Initial Count Register`](LocalApic::timer_icr)
+	/// is ignored.
+	#[inline]
+	pub fn uses_tsc_deadline(&self) -> bool {
+		self.timer_lvt.timer_mode() == TimerMode::TscDeadline
+	}
+
+	/// Returns the progress of the timer as a `(remaining, total)`
+	/// pair of ticks, taken from the
+	/// [`Timer Current Count Register`](LocalApic::timer_ccr) and
+	/// the [`Timer Initial Count Register`](LocalApic::timer_icr)
+	/// respectively. Returns `None` if the initial count is zero,
+	/// i.e. if the timer is stopped.
+	#[inline]
+	pub fn timer_progress(&self) -> Option<(u32, u32)> {
+		let total = self.timer_icr.count();
+		if total == 0 {
+			return None;
+		}
+		Some((self.timer_ccr.count(), total))
+	}
+
+	/// Returns the fraction of the timer period that remains, in the
+	/// `0.0..=1.0` range. A current count above the initial count
+	/// is reported as `1.0`. Returns `None` if the initial count is
+	/// zero. See [`LocalApic::timer_progress`] for a version that
+	/// does not use floating point.
+	#[inline]
+	pub fn timer_fraction_remaining(&self) -> Option<f32> {
+		let (remaining, total) = self.timer_progress()?;
+		Some((remaining as f32 / total as f32).min(1.0))
+	}
+
+	fn configure_timer(
+		&mut self,
+		mode: TimerMode,
+		vector: u8,
+		divisor: TimerDivisor,
+		initial_count: u32,
+	) {
+		self.timer_dcr = TimerDivConf::new().with_divide_by(divisor);
+		self.timer_lvt =
+			TimerLVT::new().with_vector(vector).with_timer_mode(mode);
+		self.timer_icr = TimerCount::new().with_count(initial_count);
+	}
+
+	/// Returns an iterator over the vectors that are requested in
+	/// the [`IRR`](LocalApic::interrupt_request) but not in service
+	/// in the [`ISR`](LocalApic::in_service), in ascending order.
+	pub fn pending_not_in_service(&self) -> impl Iterator<Item = u8> {
+		let irr = self.interrupt_request;
+		let isr = self.in_service;
+		(0..=u8::MAX).filter(move |v| {
+			irr.is_vector_set(*v) && !isr.is_vector_set(*v)
+		})
+	}
+
+	/// Returns the vector the APIC would dispatch next to the
+	/// processor, if any, emulating its priority arbitration.
+	///
+	/// The priority class of a vector is its upper 4 bits. The
+	/// processor priority class is the highest of the
+	/// [task priority](LocalApic::task_priority) class and the class
+	/// of the highest vector in service. The highest vector requested
+	/// in the [`IRR`](LocalApic::interrupt_request) is only
+	/// dispatched if its class is strictly higher than the processor
+	/// priority class.
+	pub fn next_serviceable_vector(&self) -> Option<u8> {
+		let vector = self.interrupt_request.highest_set_vector()?;
+		let isr_class = self
+			.in_service
+			.highest_set_vector()
+			.map_or(0, |v| v >> 4);
+		let ppr_class =
+			self.task_priority.priority_class().max(isr_class);
+		(vector >> 4 > ppr_class).then_some(vector)
+	}
+
+	/// Sets the [task priority](LocalApic::task_priority) class to
+	/// the class of `min_vector`, i.e. its upper 4 bits, and clears
+	/// the sub-class.
+	///
+	/// This masks all the vectors in classes at or below the class
+	/// of `min_vector`: for example, `0x40` masks vectors `0x00` to
+	/// `0x4F`, and only vectors from `0x50` upwards are delivered.
+	#[inline]
+	pub fn set_interrupt_mask_level(&mut self, min_vector: u8) {
+		self.task_priority.set_priority(min_vector & 0xf0);
+	}
+
+	/// Emulates the side effects of a write to the
+	/// [`EOI`](LocalApic::eoi) register: the highest vector in
+	/// service is cleared from the [`ISR`](LocalApic::in_service).
+	///
+	/// Returns `None` if no vector was in service. Otherwise, the
+	/// returned [`EoiEffect`] tells the emulator whether the vector
+	/// was level-triggered according to the
+	/// [`TMR`](LocalApic::trigger_mode), in which case the EOI must
+	/// be broadcast to the I/O APICs unless
+	/// [EOI-broadcast suppression](SpuriousInterruptVector::eoi_broadcast_suppression)
+	/// is enabled.
+	pub fn handle_eoi(&mut self) -> Option<EoiEffect> {
+		let vector = self.in_service.highest_set_vector()?;
+		self.in_service.clear_vector(vector);
+		let level_triggered = self.trigger_mode.is_vector_set(vector);
+		let suppressed =
+			self.spurious_iv.is_eoi_broadcast_suppressed();
+		Some(EoiEffect {
+			vector,
+			level_triggered,
+			broadcast: level_triggered && !suppressed,
+		})
+	}
+
+	/// Checks the consistency of the registers, e.g. before loading
+	/// a restored state into a virtual machine, and returns the
+	/// first violation found.
+	///
+	/// Vectors are only checked for unmasked entries with fixed
+	/// delivery, since the vector is ignored otherwise. Note that the
+	/// all-zeros [`Default`] state is not valid, since all its LVTs
+	/// are unmasked with vector 0, unlike the
+	/// [`reset_state`](LocalApic::reset_state).
+	pub fn validate(&self) -> Result<(), ApicConfigError> {
+		let model = self.dst_format.model();
+		if DestinationModel::from_bits(model).is_none() {
+			return Err(ApicConfigError::InvalidDestinationModel(
+				model,
+			));
+		}
+
+		let modes = [
+			(LvtKind::Cmci, self.cmci_lvt.delivery_mode()),
+			(LvtKind::Thermal, self.thermal_lvt.delivery_mode()),
+			(
+				LvtKind::Performance,
+				self.performance_lvt.delivery_mode(),
+			),
+			(LvtKind::Lint0, self.lint0_lvt.delivery_mode()),
+			(LvtKind::Lint1, self.lint1_lvt.delivery_mode()),
+		];
+		if let Some((kind, _)) =
+			modes.iter().find(|(_, m)| *m == DeliveryMode::Reserved3)
+		{
+			return Err(ApicConfigError::ReservedDeliveryMode(*kind));
+		}
+
+		for (kind, lvt) in self.lvt_entries() {
+			let fixed = modes
+				.iter()
+				.find(|(k, _)| *k == kind)
+				.map_or(true, |(_, m)| *m == DeliveryMode::Fixed);
+			let vector = lvt.vector();
+			if fixed
+				&& !lvt.is_masked()
+				&& InvalidVector::check(vector).is_err()
+			{
+				return Err(ApicConfigError::IllegalVector(
+					kind, vector,
+				));
+			}
+		}
+
+		if self.timer_lvt.timer_mode() == TimerMode::Reserved {
+			return Err(ApicConfigError::ReservedTimerMode);
+		}
+
+		let divisor = self.timer_dcr.divisor();
+		if divisor & 0b100 != 0 {
+			return Err(ApicConfigError::ReservedDivisor(divisor));
+		}
+
+		Ok(())
+	}
+
+	/// Clears the [`Error Status Register`](LocalApic::error_status).
+	///
+	/// On hardware, writing the ESR also latches the errors detected
+	/// since the previous write, so it must be written before each
+	/// read. See [`VolatileApic::read_error_status`] for a helper
+	/// that implements this protocol on memory-mapped registers.
+	#[inline]
+	pub fn clear_errors(&mut self) {
+		self.error_status = ErrorStatus::new();
+	}
+
+	/// Software-enables the APIC, using `spurious_vector` as the
+	/// vector for spurious interrupts. The rest of the
+	/// [`Spurious Interrupt Vector Register`](LocalApic::spurious_iv)
+	/// is preserved.
+	#[inline]
+	pub fn software_enable(&mut self, spurious_vector: u8) {
+		self.spurious_iv = self
+			.spurious_iv
+			.with_spurious_vector(spurious_vector)
+			.enable();
+	}
+}
+
 impl fmt::Debug for LocalApic {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
 		f.debug_struct("LocalApic")
@@ -108,6 +866,7 @@ impl fmt::Debug for LocalApic {
 			.field("trigger_mode", &self.trigger_mode)
 			.field("interrupt_request", &self.interrupt_request)
 			.field("error_status", &self.error_status)
+			.field("cmci_lvt", &self.cmci_lvt)
 			.field("interrupt_cmd_low", &self.interrupt_cmd_low)
 			.field("interrupt_cmd_high", &self.interrupt_cmd_high)
 			.field("timer_lvt", &self.timer_lvt)
@@ -123,42 +882,669 @@ impl fmt::Debug for LocalApic {
 	}
 }
 
+/// Implements [`defmt::Format`] for a register, printing each of
+/// the given fields as decoded by their getters.
+#[cfg(feature = "defmt")]
+macro_rules! impl_defmt_format {
+	($($reg:ident { $($field:ident),+ })+) => {
+		$(
+			impl defmt::Format for $reg {
+				fn format(&self, f: defmt::Formatter<'_>) {
+					defmt::write!(f, "{=str} {{", stringify!($reg));
+					$(
+						defmt::write!(
+							f,
+							" {=str}: {}",
+							stringify!($field),
+							self.$field()
+						);
+					)+
+					defmt::write!(f, " }}");
+				}
+			}
+		)+
+	};
+}
+
+/// Invokes the given macro with every register type and the fields
+/// that are printed when formatting it.
+macro_rules! with_register_fields {
+	($m:ident) => {
+		$m! {
+			ApicId { apic_id }
+			ApicVersion {
+				version,
+				max_lvt,
+				supports_eoi_broadcast_suppression
+			}
+			PriorityRegister { priority }
+			EndOfInterrupt { eoi }
+			LogicalDestination { logical_dst }
+			DestinationFormat { model }
+			SpuriousInterruptVector {
+				spurious_vector,
+				apic_enabled,
+				focus_cpu,
+				eoi_broadcast_suppression
+			}
+			BitfieldRegister { bitfield }
+			ErrorStatus {
+				send_cs,
+				recv_cs,
+				send_accept,
+				recv_accept,
+				send_illegal_vector,
+				recv_illegal_vector,
+				illegal_register_addr
+			}
+			CmciLVT { vector, delivery_mode, delivery_status, mask }
+			InterruptCmdLow {
+				vector,
+				delivery_mode,
+				destination_mode,
+				delivery_status,
+				level,
+				trigger,
+				shorthand
+			}
+			InterruptCmdHigh { dst }
+			TimerLVT { vector, delivery_status, mask, timer_mode }
+			ThermalLVT { vector, delivery_mode, delivery_status, mask }
+			PerfLVT { vector, delivery_mode, delivery_status, mask }
+			LIntLVT {
+				vector,
+				delivery_mode,
+				delivery_status,
+				polarity,
+				remote_irr,
+				trigger,
+				mask
+			}
+			ErrorLVT { vector, delivery_status, mask }
+			TimerCount { count }
+			TimerDivConf { divide_by }
+			ApicBase { bsp, x2apic_enable, global_enable, base_addr }
+			X2ApicIcr {
+				vector,
+				delivery_mode,
+				destination_mode,
+				level,
+				trigger,
+				shorthand,
+				dst
+			}
+			X2ApicSelfIpi { vector }
+			ExtApicFeature {
+				ier_capable,
+				seoi_capable,
+				ext_apic_id_capable,
+				ext_lvt_count
+			}
+			ExtApicControl { ier_enable, seoi_enable, ext_apic_id_enable }
+			SpecificEoi { vector }
+			ExtendedLVT { vector, delivery_mode, delivery_status, mask }
+		}
+	};
+}
+
+#[cfg(feature = "defmt")]
+with_register_fields!(impl_defmt_format);
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for Bitmap256 {
+	fn format(&self, f: defmt::Formatter<'_>) {
+		let dwords: [u32; 8] =
+			core::array::from_fn(|i| self.0[i].bitfield());
+		defmt::write!(f, "Bitmap256 {=[?]:#010x}", dwords);
+	}
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for LocalApic {
+	fn format(&self, f: defmt::Formatter<'_>) {
+		defmt::write!(
+			f,
+			"LocalApic {{ apic_id: {}, apic_version: {}, \
+			 task_priority: {}, arb_priority: {}, \
+			 processor_priority: {}, eoi: {}, logical_dst: {}, \
+			 dst_format: {}, spurious_iv: {}, in_service: {}, \
+			 trigger_mode: {}, interrupt_request: {}, \
+			 error_status: {}, cmci_lvt: {}, \
+			 interrupt_cmd_low: {}, interrupt_cmd_high: {}, \
+			 timer_lvt: {}, thermal_lvt: {}, performance_lvt: {}, \
+			 lint0_lvt: {}, lint1_lvt: {}, error_lvt: {}, \
+			 timer_icr: {}, timer_ccr: {}, timer_dcr: {} }}",
+			self.apic_id,
+			self.apic_version,
+			self.task_priority,
+			self.arb_priority,
+			self.processor_priority,
+			self.eoi,
+			self.logical_dst,
+			self.dst_format,
+			self.spurious_iv,
+			self.in_service,
+			self.trigger_mode,
+			self.interrupt_request,
+			self.error_status,
+			self.cmci_lvt,
+			self.interrupt_cmd_low,
+			self.interrupt_cmd_high,
+			self.timer_lvt,
+			self.thermal_lvt,
+			self.performance_lvt,
+			self.lint0_lvt,
+			self.lint1_lvt,
+			self.error_lvt,
+			self.timer_icr,
+			self.timer_ccr,
+			self.timer_dcr,
+		);
+	}
+}
+
+/// A decoded register field that can be printed by the
+/// [`fmt::Display`] implementations of the registers. Integers are
+/// printed in hexadecimal, and enums by their variant name.
+trait DisplayField {
+	fn fmt_field(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result;
+}
+
+impl DisplayField for u8 {
+	fn fmt_field(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{self:#x}")
+	}
+}
+
+impl DisplayField for u32 {
+	fn fmt_field(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{self:#x}")
+	}
+}
+impl DisplayField for u64 {
+	fn fmt_field(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{self:#x}")
+	}
+}
+
+impl DisplayField for bool {
+	fn fmt_field(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		fmt::Display::fmt(self, f)
+	}
+}
+
+macro_rules! impl_display_field_enum {
+	($($ty:ident),+) => {
+		$(
+			impl DisplayField for $ty {
+				fn fmt_field(
+					&self,
+					f: &mut fmt::Formatter<'_>,
+				) -> fmt::Result {
+					fmt::Debug::fmt(self, f)
+				}
+			}
+		)+
+	};
+}
+
+impl_display_field_enum!(
+	DeliveryMode,
+	DeliveryStatus,
+	DestinationShorthand,
+	DestinationMode,
+	TriggerMode,
+	PinPolarity,
+	Level,
+	TimerMode,
+	TimerDivisor
+);
+
+/// Implements [`fmt::Display`] for a register, printing each of the
+/// given fields as decoded by their getters.
+macro_rules! impl_display {
+	($($reg:ident { $($field:ident),+ })+) => {
+		$(
+			impl fmt::Display for $reg {
+				fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+					f.write_str(concat!(stringify!($reg), " {"))?;
+					let mut sep = " ";
+					$(
+						write!(f, "{sep}{}: ", stringify!($field))?;
+						self.$field().fmt_field(f)?;
+						sep = ", ";
+					)+
+					let _ = sep;
+					f.write_str(" }")
+				}
+			}
+		)+
+	};
+}
+
+with_register_fields!(impl_display);
+
+impl fmt::Display for Bitmap256 {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str("Bitmap256 [")?;
+		for (i, reg) in self.0.iter().enumerate() {
+			if i != 0 {
+				f.write_str(", ")?;
+			}
+			write!(f, "{:#010x}", reg.bitfield())?;
+		}
+		f.write_str("]")
+	}
+}
+
+impl fmt::Display for LocalApic {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		macro_rules! fields {
+			($($field:ident),+) => {{
+				f.write_str("LocalApic {\n")?;
+				$(
+					writeln!(
+						f,
+						"\t{}: {},",
+						stringify!($field),
+						self.$field
+					)?;
+				)+
+				f.write_str("}")
+			}};
+		}
+		fields!(
+			apic_id,
+			apic_version,
+			task_priority,
+			arb_priority,
+			processor_priority,
+			eoi,
+			logical_dst,
+			dst_format,
+			spurious_iv,
+			in_service,
+			trigger_mode,
+			interrupt_request,
+			error_status,
+			cmci_lvt,
+			interrupt_cmd_low,
+			interrupt_cmd_high,
+			timer_lvt,
+			thermal_lvt,
+			performance_lvt,
+			lint0_lvt,
+			lint1_lvt,
+			error_lvt,
+			timer_icr,
+			timer_ccr,
+			timer_dcr
+		)
+	}
+}
+
 #[repr(transparent)]
-#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bytemuck", derive(Pod, Zeroable))]
+#[cfg_attr(
+	feature = "zerocopy",
+	derive(FromZeroes, FromBytes, AsBytes)
+)]
 struct Reserved([u32; 4]);
 
-/// Local APIC register.
+/// Error returned when a pointer does not have the alignment
+/// required by [`LocalApic`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct AlignmentError(
+	/// The misaligned address.
+	pub usize,
+);
+
+/// Error returned when trying to use an interrupt vector in the
+/// reserved 0-15 range, which overlaps with the exception vectors.
+/// The legal range for LVT and IPI vectors is 16-255.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct InvalidVector(
+	/// The rejected vector.
+	pub u8,
+);
+
+impl InvalidVector {
+	/// The lowest legal interrupt vector.
+	pub const MIN_VECTOR: u8 = consts::MIN_VECTOR;
+
+	/// Checks that `vector` is in the legal 16-255 range.
+	#[inline]
+	pub const fn check(vector: u8) -> Result<u8, Self> {
+		if vector < Self::MIN_VECTOR {
+			return Err(Self(vector));
+		}
+		Ok(vector)
+	}
+}
+
+impl fmt::Display for AlignmentError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"address {:#x} is not aligned to {} bytes",
+			self.0,
+			core::mem::align_of::<LocalApic>()
+		)
+	}
+}
+
+impl core::error::Error for AlignmentError {}
+
+impl fmt::Display for InvalidVector {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"interrupt vector {:#x} is in the reserved 0-15 range",
+			self.0
+		)
+	}
+}
+
+impl core::error::Error for InvalidVector {}
+
+/// Error returned when a byte slice does not have the size of a
+/// [`LocalApic`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct LengthError(
+	/// The length of the rejected slice.
+	pub usize,
+);
+
+impl fmt::Display for LengthError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"expected {:#x} bytes, got {:#x}",
+			mem::size_of::<LocalApic>(),
+			self.0
+		)
+	}
+}
+
+impl core::error::Error for LengthError {}
+
+/// Error returned when an offset does not correspond to the first
+/// dword of a register.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct InvalidOffset(
+	/// The rejected offset.
+	pub u16,
+);
+
+impl fmt::Display for InvalidOffset {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "invalid register offset {:#x}", self.0)
+	}
+}
+
+impl core::error::Error for InvalidOffset {}
+
+/// Error returned when a delivery mode is not supported by a Local
+/// Vector Table entry.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct IllegalDeliveryMode(
+	/// The rejected delivery mode.
+	pub DeliveryMode,
+);
+
+impl fmt::Display for IllegalDeliveryMode {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "delivery mode {:?} is not supported", self.0)
+	}
+}
+
+impl core::error::Error for IllegalDeliveryMode {}
+
+/// Error returned when a raw register value sets reserved bits.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ReservedBitSet(
+	/// The rejected raw value.
+	pub u8,
+);
+
+impl fmt::Display for ReservedBitSet {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "value {:#x} sets reserved bits", self.0)
+	}
+}
+
+impl core::error::Error for ReservedBitSet {}
+
+/// Error returned by [`LocalApic::update_lvt`] when the requested
+/// type does not match the entry of the given kind.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct LvtKindMismatch(
+	/// The requested entry kind.
+	pub LvtKind,
+);
+
+impl fmt::Display for LvtKindMismatch {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "type does not match the {:?} LVT", self.0)
+	}
+}
+
+impl core::error::Error for LvtKindMismatch {}
+
+/// Error returned when a timer mode is not supported by the
+/// processor.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct UnsupportedTimerMode(
+	/// The rejected timer mode.
+	pub TimerMode,
+);
+
+impl fmt::Display for UnsupportedTimerMode {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "timer mode {:?} is not supported", self.0)
+	}
+}
+
+impl core::error::Error for UnsupportedTimerMode {}
+
+/// Error returned when the parameters of an IPI are inconsistent.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum IpiConfigError {
+	/// A destination was given along with a destination shorthand
+	/// other than [`NoShorthand`](DestinationShorthand::NoShorthand),
+	/// which makes the hardware ignore it.
+	DestinationWithShorthand(DestinationShorthand),
+}
+
+impl fmt::Display for IpiConfigError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::DestinationWithShorthand(shorthand) => write!(
+				f,
+				"destination given with the {shorthand:?} shorthand"
+			),
+		}
+	}
+}
+
+impl core::error::Error for IpiConfigError {}
+
+/// Error returned by [`LocalApic::validate`], identifying the first
+/// inconsistency found in the registers.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ApicConfigError {
+	/// The [`Destination Format Register`](LocalApic::dst_format)
+	/// holds an undefined model encoding.
+	InvalidDestinationModel(u8),
+	/// A Local Vector Table entry uses the reserved delivery mode.
+	ReservedDeliveryMode(LvtKind),
+	/// An unmasked Local Vector Table entry with fixed delivery uses
+	/// a vector in the reserved 0-15 range.
+	IllegalVector(LvtKind, u8),
+	/// The [`Timer LVT`](LocalApic::timer_lvt) uses the reserved
+	/// timer mode.
+	ReservedTimerMode,
+	/// The [`Timer Divide Configuration Register`](LocalApic::timer_dcr)
+	/// sets its reserved bit.
+	ReservedDivisor(u8),
+}
+
+impl fmt::Display for ApicConfigError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::InvalidDestinationModel(model) => {
+				write!(f, "invalid destination model {model:#x}")
+			}
+			Self::ReservedDeliveryMode(kind) => {
+				write!(
+					f,
+					"{kind:?} LVT uses a reserved delivery mode"
+				)
+			}
+			Self::IllegalVector(kind, vector) => {
+				write!(
+					f,
+					"{kind:?} LVT uses illegal vector {vector:#x}"
+				)
+			}
+			Self::ReservedTimerMode => {
+				f.write_str("timer LVT uses a reserved timer mode")
+			}
+			Self::ReservedDivisor(bits) => {
+				write!(
+					f,
+					"timer divisor {bits:#x} sets a reserved bit"
+				)
+			}
+		}
+	}
+}
+
+impl core::error::Error for ApicConfigError {}
+
+/// Local APIC ID Register.
+///
+/// The ID is always decoded as the full 8-bit field in bits 31:24,
+/// as implemented by integrated xAPICs. Older Pentium and P6 family
+/// processors only implement bits 27:24, and read the rest as zero,
+/// so the 8-bit field is correct for them as well. In x2APIC mode
+/// the ID is a full 32-bit value, see [`X2Apic`].
 #[bitfield(bits = 128)]
-#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bytemuck", derive(Pod, Zeroable))]
+#[cfg_attr(
+	feature = "zerocopy",
+	derive(FromZeroes, FromBytes, AsBytes)
+)]
+#[repr(transparent)]
 pub struct ApicId {
 	#[skip]
 	__: B24,
-	pub apic_id: B4,
-	#[skip]
-	__: B4,
+	pub apic_id: u8,
 	#[skip]
 	__: B96,
 }
 
+impl ApicId {
+	/// Returns the width in bits of the
+	/// [`apic_id`](ApicId::apic_id) field in xAPIC mode.
+	#[inline]
+	pub const fn id_width() -> u32 {
+		u8::BITS
+	}
+}
+
 /// ACIC Version Register.
 #[bitfield(bits = 128)]
-#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bytemuck", derive(Pod, Zeroable))]
+#[cfg_attr(
+	feature = "zerocopy",
+	derive(FromZeroes, FromBytes, AsBytes)
+)]
+#[repr(transparent)]
 pub struct ApicVersion {
 	pub version: u8,
 	#[skip]
 	__: u8,
 	pub max_lvt: B8,
+	pub supports_eoi_broadcast_suppression: B1,
 	#[skip]
-	__: u8,
+	__: B7,
 	#[skip]
 	__: B96,
 }
 
+impl ApicVersion {
+	/// Returns the number of Local Vector Table entries supported.
+	/// The [`max_lvt`](ApicVersion::max_lvt) field holds this value
+	/// minus one.
+	#[inline]
+	pub fn max_lvt_entries(&self) -> u8 {
+		self.max_lvt().saturating_add(1)
+	}
+
+	/// Returns whether the
+	/// [`Performance Counter LVT`](LocalApic::performance_lvt) is
+	/// present, which requires 5 LVT entries.
+	#[inline]
+	pub fn has_perf_lvt(&self) -> bool {
+		self.max_lvt_entries() >= 5
+	}
+
+	/// Returns whether the [`Thermal LVT`](LocalApic::thermal_lvt) is
+	/// present, which requires 6 LVT entries.
+	#[inline]
+	pub fn has_thermal_lvt(&self) -> bool {
+		self.max_lvt_entries() >= 6
+	}
+
+	/// Returns whether the [`CMCI LVT`](LocalApic::cmci_lvt) is
+	/// present, which requires 7 LVT entries.
+	#[inline]
+	pub fn has_cmci_lvt(&self) -> bool {
+		self.max_lvt_entries() >= 7
+	}
+
+	/// Returns whether EOI-broadcast suppression is supported, which
+	/// allows setting
+	/// [`SpuriousInterruptVector::eoi_broadcast_suppression`].
+	#[inline]
+	pub fn supports_eoi_suppression(&self) -> bool {
+		self.supports_eoi_broadcast_suppression() != 0
+	}
+
+	/// Returns whether the version identifies a discrete 82489DX
+	/// APIC (versions `0x00`-`0x0F`).
+	#[inline]
+	pub fn is_discrete(&self) -> bool {
+		self.version() < 0x10
+	}
+
+	/// Returns whether the version identifies an APIC integrated in
+	/// the processor (versions `0x10`-`0x1F`).
+	#[inline]
+	pub fn is_integrated(&self) -> bool {
+		(0x10..=0x1f).contains(&self.version())
+	}
+}
+
 /// Priority structure for the [`TPR`](LocalApic::task_priority),
 /// [`APR`](LocalApic::arb_priority) or
 /// [`PPR`](LocalApic::processor_priority).
 #[bitfield(bits = 128)]
-#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bytemuck", derive(Pod, Zeroable))]
+#[cfg_attr(
+	feature = "zerocopy",
+	derive(FromZeroes, FromBytes, AsBytes)
+)]
+#[repr(transparent)]
 pub struct PriorityRegister {
 	pub priority: u8,
 	#[skip]
@@ -167,9 +1553,73 @@ pub struct PriorityRegister {
 	__: B96,
 }
 
+impl PriorityRegister {
+	/// Returns the priority class, i.e. the upper 4 bits of the
+	/// priority. Only interrupts with a vector whose upper 4 bits are
+	/// higher than this class are delivered.
+	#[inline]
+	pub fn priority_class(&self) -> u8 {
+		self.priority() >> 4
+	}
+
+	/// Returns the priority sub-class, i.e. the lower 4 bits of the
+	/// priority.
+	#[inline]
+	pub fn priority_subclass(&self) -> u8 {
+		self.priority() & 0xf
+	}
+
+	/// Sets the priority class, leaving the sub-class untouched. Only
+	/// the low 4 bits of `class` are used.
+	#[inline]
+	pub fn set_class(&mut self, class: u8) {
+		let subclass = self.priority_subclass();
+		self.set_priority((class & 0xf) << 4 | subclass);
+	}
+
+	/// Returns a copy with the priority class set to `class`. See
+	/// [`set_class`](Self::set_class).
+	#[inline]
+	#[must_use]
+	pub fn with_class(mut self, class: u8) -> Self {
+		self.set_class(class);
+		self
+	}
+}
+
+/// Priority registers are ordered by their full 8-bit priority
+/// value, so the priority class takes precedence over the sub-class.
+///
+/// Registers with the same priority but different reserved bits are
+/// ordered by their raw bytes, so that the ordering remains
+/// consistent with [`PartialEq`].
+impl Ord for PriorityRegister {
+	fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+		self.priority()
+			.cmp(&other.priority())
+			.then_with(|| self.into_bytes().cmp(&other.into_bytes()))
+	}
+}
+
+impl PartialOrd for PriorityRegister {
+	fn partial_cmp(
+		&self,
+		other: &Self,
+	) -> Option<core::cmp::Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
 /// End of Interrupt Register (EOI).
 #[bitfield(bits = 128)]
-#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bytemuck", derive(Pod, Zeroable))]
+#[cfg_attr(
+	feature = "zerocopy",
+	derive(FromZeroes, FromBytes, AsBytes)
+)]
+#[repr(transparent)]
 pub struct EndOfInterrupt {
 	pub eoi: u32,
 	#[skip]
@@ -178,7 +1628,14 @@ pub struct EndOfInterrupt {
 
 /// Logical Destination Register.
 #[bitfield(bits = 128)]
-#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bytemuck", derive(Pod, Zeroable))]
+#[cfg_attr(
+	feature = "zerocopy",
+	derive(FromZeroes, FromBytes, AsBytes)
+)]
+#[repr(transparent)]
 pub struct LogicalDestination {
 	#[skip]
 	__: B24,
@@ -187,9 +1644,110 @@ pub struct LogicalDestination {
 	__: B96,
 }
 
+impl LogicalDestination {
+	/// Returns the cluster ID of the logical destination, for the
+	/// [cluster model](DestinationModel::Cluster).
+	#[inline]
+	pub fn cluster_id(&self) -> u8 {
+		self.logical_dst() >> 4
+	}
+
+	/// Returns the logical ID mask within the cluster of the logical
+	/// destination, for the [cluster model](DestinationModel::Cluster).
+	#[inline]
+	pub fn logical_id_mask(&self) -> u8 {
+		self.logical_dst() & 0xf
+	}
+
+	/// Sets the logical destination for the
+	/// [cluster model](DestinationModel::Cluster) from a cluster ID
+	/// and a logical ID mask within the cluster. Only the low 4 bits
+	/// of each value are used.
+	#[inline]
+	pub fn set_cluster(&mut self, cluster: u8, mask: u8) {
+		self.set_logical_dst((cluster & 0xf) << 4 | (mask & 0xf));
+	}
+
+	/// Returns a copy with the logical destination set for the
+	/// [cluster model](DestinationModel::Cluster). See
+	/// [`set_cluster`](Self::set_cluster).
+	///
+	/// ```
+	/// # use lapic::LogicalDestination;
+	/// let ldr = LogicalDestination::new().with_cluster(3, 0b0010);
+	/// assert_eq!(ldr.logical_dst(), 0x32);
+	/// ```
+	#[inline]
+	#[must_use]
+	pub fn with_cluster(mut self, cluster: u8, mask: u8) -> Self {
+		self.set_cluster(cluster, mask);
+		self
+	}
+
+	/// Sets the bit for the processor with the given `index` in the
+	/// logical destination, for the
+	/// [flat model](DestinationModel::Flat).
+	///
+	/// # Panics
+	///
+	/// If `index` is not lower than 8.
+	#[inline]
+	pub fn set_cpu_bit(&mut self, index: u8) {
+		assert!(index < 8, "logical destination bit out of bounds");
+		self.set_logical_dst(self.logical_dst() | (1 << index));
+	}
+
+	/// Returns a copy with the bit for the processor with the given
+	/// `index` set, for the [flat model](DestinationModel::Flat).
+	///
+	/// ```
+	/// # use lapic::LogicalDestination;
+	/// let ldr = LogicalDestination::new().with_cpu_bit(0).with_cpu_bit(3);
+	/// assert_eq!(ldr.logical_dst(), 0x09);
+	/// ```
+	///
+	/// # Panics
+	///
+	/// If `index` is not lower than 8.
+	#[inline]
+	#[must_use]
+	pub fn with_cpu_bit(mut self, index: u8) -> Self {
+		self.set_cpu_bit(index);
+		self
+	}
+
+	/// Clears the bit for the processor with the given `index` in
+	/// the logical destination, for the
+	/// [flat model](DestinationModel::Flat).
+	///
+	/// # Panics
+	///
+	/// If `index` is not lower than 8.
+	#[inline]
+	pub fn clear_cpu_bit(&mut self, index: u8) {
+		assert!(index < 8, "logical destination bit out of bounds");
+		self.set_logical_dst(self.logical_dst() & !(1 << index));
+	}
+
+	/// Returns an iterator over the indices of the processors set in
+	/// the logical destination, for the
+	/// [flat model](DestinationModel::Flat).
+	pub fn targets(&self) -> impl Iterator<Item = u8> {
+		let dst = self.logical_dst();
+		(0..8).filter(move |i| dst & (1 << i) != 0)
+	}
+}
+
 /// Destination Format Register.
 #[bitfield(bits = 128)]
-#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bytemuck", derive(Pod, Zeroable))]
+#[cfg_attr(
+	feature = "zerocopy",
+	derive(FromZeroes, FromBytes, AsBytes)
+)]
+#[repr(transparent)]
 pub struct DestinationFormat {
 	#[skip]
 	__: B28,
@@ -198,33 +1756,215 @@ pub struct DestinationFormat {
 	__: B96,
 }
 
+impl DestinationFormat {
+	/// Returns the destination model, or `None` if the model field
+	/// holds an undefined encoding.
+	#[inline]
+	pub fn destination_model(&self) -> Option<DestinationModel> {
+		DestinationModel::from_bits(self.model())
+	}
+
+	/// Sets the destination model.
+	#[inline]
+	pub fn set_destination_model(&mut self, model: DestinationModel) {
+		self.set_model(model.to_bits());
+	}
+
+	/// Returns a copy with the destination model set to `model`.
+	#[inline]
+	#[must_use]
+	pub fn with_destination_model(
+		mut self,
+		model: DestinationModel,
+	) -> Self {
+		self.set_destination_model(model);
+		self
+	}
+}
+
 /// Spurious Interrupt Vector Register.
 #[bitfield(bits = 128)]
-#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bytemuck", derive(Pod, Zeroable))]
+#[cfg_attr(
+	feature = "zerocopy",
+	derive(FromZeroes, FromBytes, AsBytes)
+)]
+#[repr(transparent)]
 pub struct SpuriousInterruptVector {
 	pub spurious_vector: u8,
 	pub apic_enabled: B1,
 	pub focus_cpu: B1,
 	#[skip]
-	__: B22,
+	__: B2,
+	/// Whether EOIs for level-triggered interrupts are not broadcast
+	/// to the I/O APICs. Only available if supported, as reported by
+	/// [`ApicVersion::supports_eoi_suppression`].
+	pub eoi_broadcast_suppression: B1,
+	#[skip]
+	__: B19,
 	#[skip]
 	__: B96,
 }
 
-/// Bitfield structure for the
-/// [`ISR`](LocalApic::in_service), [`TMR`](LocalApic::trigger_mode)
+impl SpuriousInterruptVector {
+	/// Returns a copy with the APIC software-enabled.
+	#[inline]
+	#[must_use]
+	pub fn enable(self) -> Self {
+		self.with_apic_enabled(1)
+	}
+
+	/// Returns a copy with the APIC software-disabled.
+	#[inline]
+	#[must_use]
+	pub fn disable(self) -> Self {
+		self.with_apic_enabled(0)
+	}
+
+	/// Returns whether the APIC is software-enabled.
+	#[inline]
+	pub fn is_enabled(&self) -> bool {
+		self.apic_enabled() != 0
+	}
+
+	/// Returns whether focus processor checking is enabled for
+	/// lowest priority delivery.
+	///
+	/// Note that the polarity of the raw
+	/// [`focus_cpu`](Self::focus_cpu) bit is inverted: setting it to
+	/// 1 *disables* focus processor checking.
+	#[inline]
+	pub fn focus_processor_checking_enabled(&self) -> bool {
+		self.focus_cpu() == 0
+	}
+
+	/// Enables or disables focus processor checking, taking care of
+	/// the inverted polarity of the raw
+	/// [`focus_cpu`](Self::focus_cpu) bit.
+	#[inline]
+	pub fn set_focus_processor_checking(&mut self, enabled: bool) {
+		self.set_focus_cpu(u8::from(!enabled));
+	}
+
+	/// Returns a copy with focus processor checking enabled or
+	/// disabled. See
+	/// [`set_focus_processor_checking`](Self::set_focus_processor_checking).
+	#[inline]
+	#[must_use]
+	pub fn with_focus_processor_checking(
+		mut self,
+		enabled: bool,
+	) -> Self {
+		self.set_focus_processor_checking(enabled);
+		self
+	}
+
+	/// Returns whether EOI-broadcast suppression is enabled.
+	#[inline]
+	pub fn is_eoi_broadcast_suppressed(&self) -> bool {
+		self.eoi_broadcast_suppression() != 0
+	}
+}
+
+/// Bitfield structure for the
+/// [`ISR`](LocalApic::in_service), [`TMR`](LocalApic::trigger_mode)
 /// or [`IRR`](LocalApic::interrupt_request).
 #[bitfield(bits = 128)]
-#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bytemuck", derive(Pod, Zeroable))]
+#[cfg_attr(
+	feature = "zerocopy",
+	derive(FromZeroes, FromBytes, AsBytes)
+)]
+#[repr(transparent)]
 pub struct BitfieldRegister {
 	pub bitfield: u32,
 	#[skip]
 	__: B96,
 }
 
+impl BitfieldRegister {
+	/// Returns whether the bit at index `idx` (0-31) is set.
+	///
+	/// `idx` must be lower than 32, which is checked in debug builds.
+	/// In release builds only its low 5 bits are used.
+	#[inline]
+	pub fn get_bit(&self, idx: u8) -> bool {
+		debug_assert!(idx < 32, "bit index out of bounds");
+		self.bitfield() & (1 << (idx % 32)) != 0
+	}
+
+	/// Sets or clears the bit at index `idx` (0-31).
+	///
+	/// `idx` must be lower than 32, which is checked in debug builds.
+	/// In release builds only its low 5 bits are used.
+	#[inline]
+	pub fn set_bit(&mut self, idx: u8, val: bool) {
+		debug_assert!(idx < 32, "bit index out of bounds");
+		let mask = 1 << (idx % 32);
+		let bits = self.bitfield();
+		self.set_bitfield(if val {
+			bits | mask
+		} else {
+			bits & !mask
+		});
+	}
+}
+
+/// Generates accessors for the raw dwords of the given bitmap
+/// registers of [`LocalApic`].
+macro_rules! impl_bitmap_dwords {
+	($($field:ident: $get:ident, $set:ident, $name:literal;)+) => {
+		impl LocalApic {
+			$(
+				#[doc = concat!(
+					"Returns dword `n` of the ", $name, ", which holds ",
+					"the bits for vectors `32 * n` to `32 * n + 31`.",
+					"\n\n# Panics\n\nIf `n` is not lower than 8."
+				)]
+				#[inline]
+				pub fn $get(&self, n: usize) -> u32 {
+					assert!(n < 8, "bitmap register index out of bounds");
+					self.$field[n].bitfield()
+				}
+
+				#[doc = concat!(
+					"Sets dword `n` of the ", $name, ", which holds ",
+					"the bits for vectors `32 * n` to `32 * n + 31`.",
+					"\n\n# Panics\n\nIf `n` is not lower than 8."
+				)]
+				#[inline]
+				pub fn $set(&mut self, n: usize, val: u32) {
+					assert!(n < 8, "bitmap register index out of bounds");
+					self.$field[n].set_bitfield(val);
+				}
+			)+
+		}
+	};
+}
+
+impl_bitmap_dwords! {
+	in_service: in_service_dword, set_in_service_dword,
+		"In-Service Register (ISR)";
+	trigger_mode: trigger_mode_dword, set_trigger_mode_dword,
+		"Trigger Mode Register (TMR)";
+	interrupt_request: interrupt_request_dword,
+		set_interrupt_request_dword, "Interrupt Request Register (IRR)";
+}
+
 /// Error Status Register (ESR).
 #[bitfield(bits = 128)]
-#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bytemuck", derive(Pod, Zeroable))]
+#[cfg_attr(
+	feature = "zerocopy",
+	derive(FromZeroes, FromBytes, AsBytes)
+)]
+#[repr(transparent)]
 pub struct ErrorStatus {
 	pub send_cs: B1,
 	pub recv_cs: B1,
@@ -241,21 +1981,101 @@ pub struct ErrorStatus {
 	__: B96,
 }
 
+impl ErrorStatus {
+	/// Returns whether `error` is flagged in this register.
+	pub fn has_error(&self, error: ApicError) -> bool {
+		let bits = bytes_to_dword(self.into_bytes());
+		bits & (1 << error as u32) != 0
+	}
+
+	/// Returns an iterator over the errors flagged in this register,
+	/// in bit order.
+	pub fn errors(&self) -> impl Iterator<Item = ApicError> {
+		let status = *self;
+		ApicError::ALL
+			.into_iter()
+			.filter(move |e| status.has_error(*e))
+	}
+
+	/// Returns an iterator over the descriptions of the errors
+	/// flagged in this register, in bit order. See
+	/// [`ApicError::message`].
+	pub fn messages(&self) -> impl Iterator<Item = &'static str> {
+		self.errors().map(ApicError::message)
+	}
+
+	/// Returns whether no errors are flagged in this register.
+	pub fn is_clear(&self) -> bool {
+		self.errors().next().is_none()
+	}
+}
+
+/// Corrected Machine Check Interrupt (CMCI) Local Vector Table
+/// Entry.
+#[bitfield(bits = 128)]
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bytemuck", derive(Pod, Zeroable))]
+#[cfg_attr(
+	feature = "zerocopy",
+	derive(FromZeroes, FromBytes, AsBytes)
+)]
+#[repr(transparent)]
+pub struct CmciLVT {
+	pub vector: u8,
+	#[bits = 3]
+	pub delivery_mode: DeliveryMode,
+	#[skip]
+	__: B1,
+	/// Whether the interrupt is still pending delivery. This bit is
+	/// read-only, so no setter is provided.
+	#[bits = 1]
+	#[skip(setters)]
+	pub delivery_status: DeliveryStatus,
+	#[skip]
+	__: B3,
+	pub mask: B1,
+	#[skip]
+	__: B15,
+	#[skip]
+	__: B96,
+}
+
 /// Interrupt Command Register Low (bits 31:0).
 #[bitfield(bits = 128)]
-#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bytemuck", derive(Pod, Zeroable))]
+#[cfg_attr(
+	feature = "zerocopy",
+	derive(FromZeroes, FromBytes, AsBytes)
+)]
+#[repr(transparent)]
 pub struct InterruptCmdLow {
 	pub vector: u8,
-	pub delivery_mode: B3,
-	pub destination_mode: B1,
-	pub delivery_status: B1,
+	#[bits = 3]
+	pub delivery_mode: DeliveryMode,
+	#[bits = 1]
+	pub destination_mode: DestinationMode,
+	/// Whether the interrupt is still pending delivery. This bit is
+	/// read-only, so no setter is provided.
+	#[bits = 1]
+	#[skip(setters)]
+	pub delivery_status: DeliveryStatus,
 	#[skip]
 	__: B1,
-	pub level: B1,
-	pub trigger: B1,
+	/// The INIT level de-assert sequence ([`DeliveryMode::Init`] with
+	/// [`Level::Deassert`]) is a legacy mechanism only required by
+	/// older processors. All other IPIs should use
+	/// [`Level::Assert`].
+	#[bits = 1]
+	pub level: Level,
+	#[bits = 1]
+	pub trigger: TriggerMode,
 	#[skip]
 	__: B2,
-	pub shorthand: B2,
+	#[bits = 2]
+	pub shorthand: DestinationShorthand,
 	#[skip]
 	__: B12,
 	#[skip]
@@ -264,7 +2084,14 @@ pub struct InterruptCmdLow {
 
 /// Interrupt Command Register High (bits 63:32).
 #[bitfield(bits = 128)]
-#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bytemuck", derive(Pod, Zeroable))]
+#[cfg_attr(
+	feature = "zerocopy",
+	derive(FromZeroes, FromBytes, AsBytes)
+)]
+#[repr(transparent)]
 pub struct InterruptCmdHigh {
 	#[skip]
 	__: B24,
@@ -274,32 +2101,79 @@ pub struct InterruptCmdHigh {
 }
 
 /// Timer Local Vector Table Entry.
+///
+/// This entry has no delivery mode field, since timer interrupts
+/// always use fixed delivery.
 #[bitfield(bits = 128)]
-#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bytemuck", derive(Pod, Zeroable))]
+#[cfg_attr(
+	feature = "zerocopy",
+	derive(FromZeroes, FromBytes, AsBytes)
+)]
+#[repr(transparent)]
 pub struct TimerLVT {
 	pub vector: u8,
 	#[skip]
 	__: B4,
-	pub delivery_status: B1,
+	/// Whether the interrupt is still pending delivery. This bit is
+	/// read-only, so no setter is provided.
+	#[bits = 1]
+	#[skip(setters)]
+	pub delivery_status: DeliveryStatus,
 	#[skip]
 	__: B3,
 	pub mask: B1,
-	pub timer_mode: B1,
+	#[bits = 2]
+	pub timer_mode: TimerMode,
 	#[skip]
-	__: B14,
+	__: B13,
 	#[skip]
 	__: B96,
 }
 
+impl TimerLVT {
+	/// Sets the timer to [TSC-deadline mode](TimerMode::TscDeadline)
+	/// if `supported` is true, or returns an error otherwise.
+	///
+	/// Support for this mode is reported by `CPUID.01H:ECX[24]`,
+	/// which this crate cannot query, so the caller must pass in the
+	/// result of that check.
+	#[inline]
+	pub fn try_set_tsc_deadline(
+		&mut self,
+		supported: bool,
+	) -> Result<(), UnsupportedTimerMode> {
+		if !supported {
+			return Err(UnsupportedTimerMode(TimerMode::TscDeadline));
+		}
+		self.set_timer_mode(TimerMode::TscDeadline);
+		Ok(())
+	}
+}
+
 /// Thermal Local Vector Table Entry.
 #[bitfield(bits = 128)]
-#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bytemuck", derive(Pod, Zeroable))]
+#[cfg_attr(
+	feature = "zerocopy",
+	derive(FromZeroes, FromBytes, AsBytes)
+)]
+#[repr(transparent)]
 pub struct ThermalLVT {
 	pub vector: u8,
-	pub delivery_mode: B3,
+	#[bits = 3]
+	pub delivery_mode: DeliveryMode,
 	#[skip]
 	__: B1,
-	pub delivery_status: B1,
+	/// Whether the interrupt is still pending delivery. This bit is
+	/// read-only, so no setter is provided.
+	#[bits = 1]
+	#[skip(setters)]
+	pub delivery_status: DeliveryStatus,
 	#[skip]
 	__: B3,
 	pub mask: B1,
@@ -311,13 +2185,25 @@ pub struct ThermalLVT {
 
 /// Performance Counter Local Vector Table Entry.
 #[bitfield(bits = 128)]
-#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bytemuck", derive(Pod, Zeroable))]
+#[cfg_attr(
+	feature = "zerocopy",
+	derive(FromZeroes, FromBytes, AsBytes)
+)]
+#[repr(transparent)]
 pub struct PerfLVT {
 	pub vector: u8,
-	pub delivery_mode: B3,
+	#[bits = 3]
+	pub delivery_mode: DeliveryMode,
 	#[skip]
 	__: B1,
-	pub delivery_status: B1,
+	/// Whether the interrupt is still pending delivery. This bit is
+	/// read-only, so no setter is provided.
+	#[bits = 1]
+	#[skip(setters)]
+	pub delivery_status: DeliveryStatus,
 	#[skip]
 	__: B3,
 	pub mask: B1,
@@ -330,16 +2216,34 @@ pub struct PerfLVT {
 /// Structure for [Local Interrupt 0](LocalApic::lint0_lvt) and
 /// [1](LocalApic::lint1_lvt) Vector Table Entries.
 #[bitfield(bits = 128)]
-#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bytemuck", derive(Pod, Zeroable))]
+#[cfg_attr(
+	feature = "zerocopy",
+	derive(FromZeroes, FromBytes, AsBytes)
+)]
+#[repr(transparent)]
 pub struct LIntLVT {
 	pub vector: u8,
-	pub delivery_mode: B3,
+	#[bits = 3]
+	pub delivery_mode: DeliveryMode,
 	#[skip]
 	__: B1,
-	pub delivery_status: B1,
-	pub polarity: B1,
-	pub remote_irr: B1,
-	pub trigger: B1,
+	/// Whether the interrupt is still pending delivery. This bit is
+	/// read-only, so no setter is provided.
+	#[bits = 1]
+	#[skip(setters)]
+	pub delivery_status: DeliveryStatus,
+	#[bits = 1]
+	pub polarity: PinPolarity,
+	/// Set by the hardware when a level-triggered interrupt is
+	/// accepted, and cleared on EOI. This bit is read-only, so no
+	/// setter is provided.
+	#[skip(setters)]
+	pub remote_irr: bool,
+	#[bits = 1]
+	pub trigger: TriggerMode,
 	pub mask: B1,
 	#[skip]
 	__: B15,
@@ -348,13 +2252,27 @@ pub struct LIntLVT {
 }
 
 /// Error Vector Table Entry.
+///
+/// This entry has no delivery mode field, since error interrupts
+/// always use fixed delivery.
 #[bitfield(bits = 128)]
-#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bytemuck", derive(Pod, Zeroable))]
+#[cfg_attr(
+	feature = "zerocopy",
+	derive(FromZeroes, FromBytes, AsBytes)
+)]
+#[repr(transparent)]
 pub struct ErrorLVT {
 	pub vector: u8,
 	#[skip]
 	__: B4,
-	pub delivery_status: B1,
+	/// Whether the interrupt is still pending delivery. This bit is
+	/// read-only, so no setter is provided.
+	#[bits = 1]
+	#[skip(setters)]
+	pub delivery_status: DeliveryStatus,
 	#[skip]
 	__: B3,
 	pub mask: B1,
@@ -367,7 +2285,14 @@ pub struct ErrorLVT {
 /// Structure for [Initial](LocalApic::timer_icr) and
 /// [Current](LocalApic::timer_ccr) Timer Count Registers.
 #[bitfield(bits = 128)]
-#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bytemuck", derive(Pod, Zeroable))]
+#[cfg_attr(
+	feature = "zerocopy",
+	derive(FromZeroes, FromBytes, AsBytes)
+)]
+#[repr(transparent)]
 pub struct TimerCount {
 	pub count: u32,
 	#[skip]
@@ -376,8 +2301,18 @@ pub struct TimerCount {
 
 /// Timer Divide Configuration Register.
 #[bitfield(bits = 128)]
-#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bytemuck", derive(Pod, Zeroable))]
+#[cfg_attr(
+	feature = "zerocopy",
+	derive(FromZeroes, FromBytes, AsBytes)
+)]
+#[repr(transparent)]
 pub struct TimerDivConf {
+	/// Raw divisor encoding, whose bit 2 is reserved and must be
+	/// zero. Prefer the typed [`TimerDivConf::divide_by`] accessors,
+	/// or [`TimerDivConf::try_set_raw_divisor`] for raw writes.
 	pub divisor: B4,
 	#[skip]
 	__: B28,
@@ -385,49 +2320,2701 @@ pub struct TimerDivConf {
 	__: B96,
 }
 
-#[cfg(test)]
-mod tests {
-	use super::*;
-	use core::mem;
-	use memoffset::offset_of;
+impl TimerDivConf {
+	/// Returns the value the timer clock is divided by. The reserved
+	/// bit 2 of the divisor field is ignored.
+	#[inline]
+	pub fn divide_by(&self) -> TimerDivisor {
+		TimerDivisor::decode(self.divisor())
+	}
 
-	#[test]
-	fn test_offsets() {
-		// Offsets from Table 16-2. APIC Registers - AMD Architecture
-		// Programmer's Manual Vol. 2: System Programming
-		assert_eq!(offset_of!(LocalApic, apic_id), 0x20);
-		assert_eq!(offset_of!(LocalApic, apic_version), 0x30);
-		assert_eq!(offset_of!(LocalApic, task_priority), 0x80);
-		assert_eq!(offset_of!(LocalApic, arb_priority), 0x90);
-		assert_eq!(offset_of!(LocalApic, processor_priority), 0xa0);
-		assert_eq!(offset_of!(LocalApic, eoi), 0xb0);
-		assert_eq!(offset_of!(LocalApic, logical_dst), 0xd0);
-		assert_eq!(offset_of!(LocalApic, dst_format), 0xe0);
-		assert_eq!(offset_of!(LocalApic, spurious_iv), 0xf0);
-		assert_eq!(offset_of!(LocalApic, in_service), 0x100);
-		assert_eq!(offset_of!(LocalApic, trigger_mode), 0x180);
-		assert_eq!(offset_of!(LocalApic, interrupt_request), 0x200);
-		assert_eq!(offset_of!(LocalApic, error_status), 0x280);
-		assert_eq!(offset_of!(LocalApic, interrupt_cmd_low), 0x300);
-		assert_eq!(offset_of!(LocalApic, interrupt_cmd_high), 0x310);
-		assert_eq!(offset_of!(LocalApic, timer_lvt), 0x320);
-		assert_eq!(offset_of!(LocalApic, thermal_lvt), 0x330);
-		assert_eq!(offset_of!(LocalApic, performance_lvt), 0x340);
-		assert_eq!(offset_of!(LocalApic, lint0_lvt), 0x350);
-		assert_eq!(offset_of!(LocalApic, lint1_lvt), 0x360);
-		assert_eq!(offset_of!(LocalApic, error_lvt), 0x370);
-		assert_eq!(offset_of!(LocalApic, timer_icr), 0x380);
-		assert_eq!(offset_of!(LocalApic, timer_ccr), 0x390);
-		assert_eq!(offset_of!(LocalApic, timer_dcr), 0x3e0);
+	/// Sets the value the timer clock is divided by.
+	#[inline]
+	pub fn set_divide_by(&mut self, div: TimerDivisor) {
+		self.set_divisor(div.to_bits());
+	}
 
-		assert_eq!(mem::size_of::<LocalApic>(), 0x400);
+	/// Returns a copy with the value the timer clock is divided by
+	/// set to `div`.
+	#[inline]
+	#[must_use]
+	pub fn with_divide_by(mut self, div: TimerDivisor) -> Self {
+		self.set_divide_by(div);
+		self
 	}
 
-	#[test]
-	fn set_field() {
-		let id = ApicId::default().with_apic_id(3);
-		let bytes = id.into_bytes()[..4].try_into().unwrap();
-		let val = u32::from_le_bytes(bytes);
-		assert_eq!(val, 0x3000000);
+	/// Sets the raw divisor encoding, rejecting values that set the
+	/// reserved bit 2 or bits outside of the field.
+	#[inline]
+	pub fn try_set_raw_divisor(
+		&mut self,
+		bits: u8,
+	) -> Result<(), ReservedBitSet> {
+		if bits & !0b1011 != 0 {
+			return Err(ReservedBitSet(bits));
+		}
+		self.set_divisor(bits);
+		Ok(())
+	}
+}
+
+/// `IA32_APIC_BASE` Model-Specific Register (MSR `0x1B`).
+///
+/// This register holds the physical base address of the local APIC
+/// registers, along with the global and x2APIC enable bits and the
+/// bootstrap processor (BSP) flag. It can be converted to and from
+/// the raw `u64` value of the MSR.
+#[bitfield(bits = 64)]
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bytemuck", derive(Pod, Zeroable))]
+#[cfg_attr(
+	feature = "zerocopy",
+	derive(FromZeroes, FromBytes, AsBytes)
+)]
+#[repr(transparent)]
+pub struct ApicBase {
+	#[skip]
+	__: B8,
+	pub bsp: B1,
+	#[skip]
+	__: B1,
+	pub x2apic_enable: B1,
+	pub global_enable: B1,
+	pub base_addr: B40,
+	#[skip]
+	__: B12,
+}
+
+impl ApicBase {
+	/// Returns the physical base address of the local APIC
+	/// registers.
+	#[inline]
+	pub fn base_address(&self) -> u64 {
+		self.base_addr() << 12
+	}
+
+	/// Returns whether the current processor is the bootstrap
+	/// processor (BSP).
+	#[inline]
+	pub fn is_bootstrap_processor(&self) -> bool {
+		self.bsp() != 0
+	}
+
+	/// Returns whether the local APIC is in x2APIC mode.
+	#[inline]
+	pub fn is_x2apic(&self) -> bool {
+		self.x2apic_enable() != 0
+	}
+
+	/// Returns whether the local APIC is globally enabled.
+	#[inline]
+	pub fn is_global_enabled(&self) -> bool {
+		self.global_enable() != 0
+	}
+}
+
+impl From<u64> for ApicBase {
+	#[inline]
+	fn from(val: u64) -> Self {
+		Self::from_bytes(val.to_le_bytes())
+	}
+}
+
+impl From<ApicBase> for u64 {
+	#[inline]
+	fn from(val: ApicBase) -> Self {
+		u64::from_le_bytes(val.into_bytes())
+	}
+}
+
+/// `IA32_TSC_DEADLINE` Model-Specific Register (MSR `0x6E0`).
+///
+/// When the [`Timer LVT`](LocalApic::timer_lvt) is in
+/// [`TscDeadline`](TimerMode::TscDeadline) mode, the
+/// [`Timer Initial Count Register`](LocalApic::timer_icr) is ignored,
+/// and the timer instead fires when the time-stamp counter reaches
+/// the value written to this MSR. Writing zero disarms the timer.
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TscDeadline(
+	/// The TSC value at which the timer fires.
+	pub u64,
+);
+
+impl TscDeadline {
+	/// Address of the `IA32_TSC_DEADLINE` MSR.
+	pub const MSR: u32 = consts::IA32_TSC_DEADLINE_MSR;
+
+	/// Reads the MSR through `msr`.
+	#[inline]
+	pub fn read<M: MsrAccess>(msr: &M) -> Self {
+		Self(msr.read_msr(Self::MSR))
+	}
+
+	/// Writes the MSR through `msr`, arming the timer if it is in
+	/// TSC-deadline mode.
+	#[inline]
+	pub fn write<M: MsrAccess>(self, msr: &M) {
+		msr.write_msr(Self::MSR, self.0);
+	}
+}
+
+/// Common operations on the Local Vector Table entries, which allow
+/// writing code generic over all of them.
+///
+/// The consuming helpers are named `masked` and `unmasked` since
+/// every LVT type already has a `mask()` getter for the raw bit.
+pub trait MaskableLvt {
+	/// Returns the interrupt vector of the entry.
+	fn vector(&self) -> u8;
+
+	/// Sets the interrupt vector of the entry.
+	fn set_vector(&mut self, v: u8);
+
+	/// Returns whether the entry is masked, i.e. whether the
+	/// interrupt is prevented from being delivered.
+	fn is_masked(&self) -> bool;
+
+	/// Sets or clears the mask bit of the entry.
+	fn set_masked(&mut self, b: bool);
+
+	/// Returns a copy of the entry with the mask bit set, so that
+	/// the interrupt is not delivered.
+	#[inline]
+	#[must_use]
+	fn masked(mut self) -> Self
+	where
+		Self: Sized,
+	{
+		self.set_masked(true);
+		self
+	}
+
+	/// Returns a copy of the entry with the mask bit cleared, so
+	/// that the interrupt is delivered.
+	#[inline]
+	#[must_use]
+	fn unmasked(mut self) -> Self
+	where
+		Self: Sized,
+	{
+		self.set_masked(false);
+		self
+	}
+}
+
+/// Implements [`MaskableLvt`] for the given LVT registers.
+macro_rules! impl_maskable_lvt {
+	($($reg:ident),+ $(,)?) => {
+		$(
+			impl MaskableLvt for $reg {
+				#[inline]
+				fn vector(&self) -> u8 {
+					$reg::vector(self)
+				}
+
+				#[inline]
+				fn set_vector(&mut self, v: u8) {
+					$reg::set_vector(self, v)
+				}
+
+				#[inline]
+				fn is_masked(&self) -> bool {
+					self.mask() != 0
+				}
+
+				#[inline]
+				fn set_masked(&mut self, b: bool) {
+					self.set_mask(b.into())
+				}
+			}
+		)+
+	};
+}
+
+impl_maskable_lvt!(
+	TimerLVT,
+	ThermalLVT,
+	PerfLVT,
+	LIntLVT,
+	ErrorLVT,
+	CmciLVT,
+	ExtendedLVT,
+);
+
+/// A Local Vector Table entry stored in [`LocalApic`], which can
+/// be accessed by its [`LvtKind`].
+pub trait LvtEntry: MaskableLvt + Copy {
+	/// Returns the entry of the given kind, or `None` if that entry
+	/// is not of type `Self`.
+	fn entry_mut(
+		apic: &mut LocalApic,
+		kind: LvtKind,
+	) -> Option<&mut Self>;
+}
+
+/// Implements [`LvtEntry`] for the given LVT registers, each
+/// mapped to the kinds and [`LocalApic`] fields that hold it.
+macro_rules! impl_lvt_entry {
+	($($reg:ident => { $($kind:ident: $field:ident),+ $(,)? }),+ $(,)?) => {
+		$(
+			impl LvtEntry for $reg {
+				#[inline]
+				fn entry_mut(
+					apic: &mut LocalApic,
+					kind: LvtKind,
+				) -> Option<&mut Self> {
+					match kind {
+						$(LvtKind::$kind => Some(&mut apic.$field),)+
+						_ => None,
+					}
+				}
+			}
+		)+
+	};
+}
+
+impl_lvt_entry!(
+	CmciLVT => { Cmci: cmci_lvt },
+	TimerLVT => { Timer: timer_lvt },
+	ThermalLVT => { Thermal: thermal_lvt },
+	PerfLVT => { Performance: performance_lvt },
+	LIntLVT => { Lint0: lint0_lvt, Lint1: lint1_lvt },
+	ErrorLVT => { Error: error_lvt },
+);
+
+/// The effects of an EOI, as returned by [`LocalApic::handle_eoi`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct EoiEffect {
+	/// The vector that was cleared from the ISR.
+	pub vector: u8,
+	/// Whether the vector was level-triggered.
+	pub level_triggered: bool,
+	/// Whether the EOI must be broadcast to the I/O APICs.
+	pub broadcast: bool,
+}
+
+/// Identifies one of the Local Vector Table entries in
+/// [`LocalApic`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum LvtKind {
+	/// [`LocalApic::cmci_lvt`].
+	Cmci,
+	/// [`LocalApic::timer_lvt`].
+	Timer,
+	/// [`LocalApic::thermal_lvt`].
+	Thermal,
+	/// [`LocalApic::performance_lvt`].
+	Performance,
+	/// [`LocalApic::lint0_lvt`].
+	Lint0,
+	/// [`LocalApic::lint1_lvt`].
+	Lint1,
+	/// [`LocalApic::error_lvt`].
+	Error,
+}
+
+/// An owned Local Vector Table entry of any kind, for storing
+/// heterogeneous entries without trait objects.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Lvt {
+	/// A [`LocalApic::cmci_lvt`] value.
+	Cmci(CmciLVT),
+	/// A [`LocalApic::timer_lvt`] value.
+	Timer(TimerLVT),
+	/// A [`LocalApic::thermal_lvt`] value.
+	Thermal(ThermalLVT),
+	/// A [`LocalApic::performance_lvt`] value.
+	Performance(PerfLVT),
+	/// A [`LocalApic::lint0_lvt`] value.
+	Lint0(LIntLVT),
+	/// A [`LocalApic::lint1_lvt`] value.
+	Lint1(LIntLVT),
+	/// A [`LocalApic::error_lvt`] value.
+	Error(ErrorLVT),
+}
+
+impl Lvt {
+	/// Returns the kind of the entry.
+	pub fn kind(&self) -> LvtKind {
+		match self {
+			Self::Cmci(_) => LvtKind::Cmci,
+			Self::Timer(_) => LvtKind::Timer,
+			Self::Thermal(_) => LvtKind::Thermal,
+			Self::Performance(_) => LvtKind::Performance,
+			Self::Lint0(_) => LvtKind::Lint0,
+			Self::Lint1(_) => LvtKind::Lint1,
+			Self::Error(_) => LvtKind::Error,
+		}
+	}
+
+	/// Returns the inner entry as a trait object.
+	pub fn as_maskable(&self) -> &dyn MaskableLvt {
+		match self {
+			Self::Cmci(lvt) => lvt,
+			Self::Timer(lvt) => lvt,
+			Self::Thermal(lvt) => lvt,
+			Self::Performance(lvt) => lvt,
+			Self::Lint0(lvt) | Self::Lint1(lvt) => lvt,
+			Self::Error(lvt) => lvt,
+		}
+	}
+
+	/// Returns the interrupt vector of the entry.
+	#[inline]
+	pub fn vector(&self) -> u8 {
+		self.as_maskable().vector()
+	}
+
+	/// Returns whether the entry is masked.
+	#[inline]
+	pub fn is_masked(&self) -> bool {
+		self.as_maskable().is_masked()
+	}
+}
+
+/// Implements a checked vector setter for the given registers,
+/// optionally appending the given bracketed doc comments.
+macro_rules! impl_try_set_vector {
+	(@impl $reg:ident [$(#[$attr:meta])*]) => {
+		impl $reg {
+			/// Sets the interrupt vector, rejecting vectors in the
+			/// reserved 0-15 range. The legal range is 16-255.
+			///
+			/// Use the unchecked `set_vector` in performance-sensitive
+			/// paths.
+			$(#[$attr])*
+			#[inline]
+			pub fn try_set_vector(
+				&mut self,
+				v: u8,
+			) -> Result<(), InvalidVector> {
+				self.set_vector(InvalidVector::check(v)?);
+				Ok(())
+			}
+
+			/// Returns a copy with the interrupt vector set to `v`,
+			/// rejecting vectors in the reserved 0-15 range. This is
+			/// the consuming variant of `try_set_vector`, not to be
+			/// confused with the generated `with_vector_checked`,
+			/// which only checks that the value fits in the field.
+			$(#[$attr])*
+			#[inline]
+			pub fn try_with_vector(
+				mut self,
+				v: u8,
+			) -> Result<Self, InvalidVector> {
+				self.try_set_vector(v)?;
+				Ok(self)
+			}
+		}
+	};
+	($docs:tt $($reg:ident),+ $(,)?) => {
+		$(impl_try_set_vector!(@impl $reg $docs);)+
+	};
+}
+
+impl_try_set_vector!(
+	[]
+	TimerLVT,
+	ThermalLVT,
+	PerfLVT,
+	LIntLVT,
+	ErrorLVT,
+	CmciLVT,
+	ExtendedLVT,
+);
+
+impl_try_set_vector!(
+	[
+		///
+		/// For [`DeliveryMode::Startup`] IPIs the vector holds the
+		/// page number of the startup code, which may legitimately be
+		/// lower than 16, so the unchecked setter must be used
+		/// instead.
+	]
+	InterruptCmdLow,
+	X2ApicIcr,
+);
+
+/// Implements a checked delivery mode setter for the given Local
+/// Vector Table entries, accepting only the given modes.
+macro_rules! impl_try_set_delivery_mode {
+	($($reg:ident [$($mode:ident),+];)+) => {
+		$(
+			impl $reg {
+				/// Sets the delivery mode, rejecting modes that are not
+				/// supported by this entry. The supported modes are:
+				$(#[doc = concat!(
+					"* [`", stringify!($mode), "`](DeliveryMode::",
+					stringify!($mode), ")"
+				)])+
+				#[inline]
+				pub fn try_set_delivery_mode(
+					&mut self,
+					mode: DeliveryMode,
+				) -> Result<(), IllegalDeliveryMode> {
+					match mode {
+						$(DeliveryMode::$mode)|+ => {
+							self.set_delivery_mode(mode);
+							Ok(())
+						}
+						_ => Err(IllegalDeliveryMode(mode)),
+					}
+				}
+			}
+		)+
+	};
+}
+
+impl_try_set_delivery_mode! {
+	CmciLVT [Fixed, Smi, Nmi];
+	ThermalLVT [Fixed, Smi, Nmi];
+	PerfLVT [Fixed, Smi, Nmi];
+	LIntLVT [Fixed, Smi, Nmi, Init, ExtInt];
+}
+
+/// Masks all the given Local Vector Table entries. Heterogeneous
+/// entries can be passed as [`MaskableLvt`] trait objects.
+///
+/// ```
+/// # use lapic::{mask_all, LocalApic, MaskableLvt};
+/// let mut apic = LocalApic::default();
+/// let lvts: [&mut dyn MaskableLvt; 2] =
+///     [&mut apic.timer_lvt, &mut apic.error_lvt];
+/// mask_all(lvts);
+/// assert!(apic.timer_lvt.is_masked());
+/// ```
+pub fn mask_all<'a, T>(lvts: impl IntoIterator<Item = &'a mut T>)
+where
+	T: MaskableLvt + ?Sized + 'a,
+{
+	for lvt in lvts {
+		lvt.set_masked(true);
+	}
+}
+
+/// Returns whether the local APIC is both globally enabled, via
+/// [`ApicBase`], and software enabled, via the
+/// [`SpuriousInterruptVector`]. Both are required for interrupts to
+/// be delivered.
+#[inline]
+pub fn is_fully_enabled(
+	base: &ApicBase,
+	siv: &SpuriousInterruptVector,
+) -> bool {
+	base.is_global_enabled() && siv.is_enabled()
+}
+
+/// Copies the priority of `src` into `dst`, leaving the rest of the
+/// bits of `dst` intact. This is useful to mirror one of the
+/// priority registers, like the
+/// [`Task Priority Register`](LocalApic::task_priority), into a
+/// shadow copy.
+#[inline]
+pub fn copy_priority(
+	src: &PriorityRegister,
+	dst: &mut PriorityRegister,
+) {
+	dst.set_priority(src.priority());
+}
+
+/// Returns the logical destination mask that targets the processor
+/// with the given physical APIC ID in the
+/// [flat model](DestinationModel::Flat), which has a single bit per
+/// processor. Returns `None` for IDs higher than 7, which cannot be
+/// addressed in this model.
+#[inline]
+pub const fn physical_to_flat_logical(apic_id: u8) -> Option<u8> {
+	if apic_id < 8 {
+		Some(1 << apic_id)
+	} else {
+		None
+	}
+}
+
+/// Returns an iterator over the physical APIC IDs targeted by the
+/// given logical destination mask in the
+/// [flat model](DestinationModel::Flat).
+#[inline]
+pub fn flat_logical_to_physical(
+	mask: u8,
+) -> impl Iterator<Item = u8> {
+	LogicalDestination::new().with_logical_dst(mask).targets()
+}
+
+/// Converts the value of the first dword of a register into its
+/// raw 16 bytes, with the rest of the bits set to zero.
+const fn dword_to_bytes(val: u32) -> [u8; 16] {
+	let dword = val.to_le_bytes();
+	let mut bytes = [0; 16];
+	let mut i = 0;
+	while i < dword.len() {
+		bytes[i] = dword[i];
+		i += 1;
+	}
+	bytes
+}
+
+/// Returns the value of the first dword of the raw bytes of a
+/// register.
+pub(crate) const fn bytes_to_dword(bytes: [u8; 16]) -> u32 {
+	u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+/// Invokes the given macro with every register type.
+macro_rules! for_each_register {
+	($m:ident) => {
+		$m!(
+			ApicId,
+			ApicVersion,
+			PriorityRegister,
+			EndOfInterrupt,
+			LogicalDestination,
+			DestinationFormat,
+			SpuriousInterruptVector,
+			BitfieldRegister,
+			ErrorStatus,
+			CmciLVT,
+			InterruptCmdLow,
+			InterruptCmdHigh,
+			TimerLVT,
+			ThermalLVT,
+			PerfLVT,
+			LIntLVT,
+			ErrorLVT,
+			TimerCount,
+			TimerDivConf,
+			ExtApicFeature,
+			ExtApicControl,
+			SpecificEoi,
+			ExtendedLVT,
+		);
+	};
+}
+
+/// Implements `const` constructors from the value of the first
+/// dword of a register.
+macro_rules! impl_from_u32 {
+	($($reg:ident),+ $(,)?) => {
+		$(
+			impl $reg {
+				/// Creates the register from the value of its first
+				/// dword, which holds all its meaningful bits. The
+				/// rest of the bits are set to zero.
+				#[inline]
+				pub const fn from_u32(val: u32) -> Self {
+					Self::from_bytes(dword_to_bytes(val))
+				}
+			}
+		)+
+	};
+}
+
+for_each_register!(impl_from_u32);
+
+/// Partial updates of a register, for read-modify-write sequences
+/// that must leave some of its bits untouched.
+///
+/// ```
+/// # use lapic::{MaskableLvt, RegisterOverlay, TimerLVT};
+/// let mut lvt = TimerLVT::new().with_vector(0x20).masked();
+/// lvt.overlay(TimerLVT::new().with_vector(0x40), 0xff);
+/// assert_eq!(lvt.vector(), 0x40);
+/// assert!(lvt.is_masked());
+/// ```
+pub trait RegisterOverlay {
+	/// Copies the bits set in `mask` from `other` into `self`,
+	/// leaving the rest of the bits of `self` intact. Bit `n` of
+	/// `mask` corresponds to bit `n` of the 16 bytes of the register,
+	/// in little-endian order.
+	fn overlay(&mut self, other: Self, mask: u128);
+}
+
+/// Implements [`RegisterOverlay`] for the given registers.
+macro_rules! impl_register_overlay {
+	($($reg:ident),+ $(,)?) => {
+		$(
+			impl RegisterOverlay for $reg {
+				#[inline]
+				fn overlay(&mut self, other: Self, mask: u128) {
+					let cur = u128::from_le_bytes(self.into_bytes());
+					let new = u128::from_le_bytes(other.into_bytes());
+					let val = (cur & !mask) | (new & mask);
+					*self = Self::from_bytes(val.to_le_bytes());
+				}
+			}
+		)+
+	};
+}
+
+for_each_register!(impl_register_overlay);
+
+/// Implements conversions between the given registers and their raw
+/// 16 bytes.
+macro_rules! impl_from_bytes {
+	($($reg:ident),+ $(,)?) => {
+		$(
+			impl From<[u8; 16]> for $reg {
+				#[inline]
+				fn from(bytes: [u8; 16]) -> Self {
+					Self::from_bytes(bytes)
+				}
+			}
+
+			impl From<$reg> for [u8; 16] {
+				#[inline]
+				fn from(reg: $reg) -> Self {
+					reg.into_bytes()
+				}
+			}
+		)+
+	};
+}
+
+for_each_register!(impl_from_bytes);
+
+/// Access to the first dword of a register, which holds all its
+/// meaningful bits and is the only part that is accessed on
+/// hardware.
+pub trait ApicRegister32:
+	Copy + From<[u8; 16]> + Into<[u8; 16]>
+{
+	/// Returns the value of the first dword of the register.
+	#[inline]
+	fn as_u32(&self) -> u32 {
+		bytes_to_dword((*self).into())
+	}
+
+	/// Creates the register from the value of its first dword, with
+	/// the rest of the bits set to zero. Each register also provides
+	/// this function as an inherent `const fn`.
+	#[inline]
+	fn from_u32(val: u32) -> Self {
+		Self::from(dword_to_bytes(val))
+	}
+}
+
+/// Implements [`ApicRegister32`] for the given registers.
+macro_rules! impl_apic_register32 {
+	($($reg:ident),+ $(,)?) => {
+		$(impl ApicRegister32 for $reg {})+
+	};
+}
+
+for_each_register!(impl_apic_register32);
+
+/// A value made of one or more 16-byte register slots, which can be
+/// stored to and loaded from the raw memory image of a
+/// [`LocalApic`]. Both methods only access the prefix of the given
+/// slice that corresponds to the value.
+trait RegisterSlots {
+	fn store(&self, out: &mut [u8]);
+	fn load(bytes: &[u8]) -> Self;
+}
+
+macro_rules! impl_register_slots {
+	($($reg:ident),+ $(,)?) => {
+		$(
+			impl RegisterSlots for $reg {
+				fn store(&self, out: &mut [u8]) {
+					out[..16].copy_from_slice(&self.into_bytes());
+				}
+
+				fn load(bytes: &[u8]) -> Self {
+					let mut raw = [0; 16];
+					raw.copy_from_slice(&bytes[..16]);
+					Self::from_bytes(raw)
+				}
+			}
+		)+
+	};
+}
+
+for_each_register!(impl_register_slots);
+
+impl RegisterSlots for u32 {
+	fn store(&self, out: &mut [u8]) {
+		out[..4].copy_from_slice(&self.to_le_bytes());
+	}
+
+	fn load(bytes: &[u8]) -> Self {
+		Self::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+	}
+}
+
+impl<T: RegisterSlots, const N: usize> RegisterSlots for [T; N] {
+	fn store(&self, out: &mut [u8]) {
+		let size = mem::size_of::<T>();
+		for (val, chunk) in
+			self.iter().zip(out.chunks_exact_mut(size))
+		{
+			val.store(chunk);
+		}
+	}
+
+	fn load(bytes: &[u8]) -> Self {
+		let size = mem::size_of::<T>();
+		core::array::from_fn(|i| T::load(&bytes[i * size..]))
+	}
+}
+
+impl RegisterSlots for Reserved {
+	fn store(&self, out: &mut [u8]) {
+		self.0.store(out);
+	}
+
+	fn load(bytes: &[u8]) -> Self {
+		Self(RegisterSlots::load(bytes))
+	}
+}
+
+impl RegisterSlots for Bitmap256 {
+	fn store(&self, out: &mut [u8]) {
+		self.0.store(out);
+	}
+
+	fn load(bytes: &[u8]) -> Self {
+		Self(RegisterSlots::load(bytes))
+	}
+}
+
+/// Implements the snapshot methods of [`LocalApic`] by storing and
+/// loading each of the given fields at its offset.
+macro_rules! impl_snapshot {
+	($($field:ident),+ $(,)?) => {
+		impl LocalApic {
+			/// Returns the memory image of the whole register page,
+			/// including reserved regions, e.g. for saving the state
+			/// of a virtual machine.
+			pub fn to_snapshot(&self) -> [u8; 0x400] {
+				let mut out = [0; 0x400];
+				$(
+					self.$field
+						.store(&mut out[mem::offset_of!(Self, $field)..]);
+				)+
+				out
+			}
+
+			/// Creates the registers from the memory image of the
+			/// whole register page, as returned by
+			/// [`to_snapshot`](Self::to_snapshot).
+			pub fn from_snapshot(
+				bytes: &[u8; 0x400],
+			) -> Self {
+				Self {
+					$(
+						$field: RegisterSlots::load(
+							&bytes[mem::offset_of!(Self, $field)..],
+						),
+					)+
+				}
+			}
+		}
+	};
+}
+
+impl LocalApic {
+	/// Creates the registers from the memory image of the whole
+	/// register page, returning an error instead of panicking if
+	/// `bytes` is not exactly `0x400` bytes long. Use
+	/// [`from_snapshot`](Self::from_snapshot) if the length is
+	/// already known to be correct.
+	pub fn try_from_bytes(bytes: &[u8]) -> Result<Self, LengthError> {
+		let bytes = <&[u8; 0x400]>::try_from(bytes)
+			.map_err(|_| LengthError(bytes.len()))?;
+		Ok(Self::from_snapshot(bytes))
+	}
+
+	/// Returns the memory image of the whole register page, like
+	/// [`to_snapshot`](Self::to_snapshot), in an allocated buffer.
+	/// Use [`try_from_bytes`](Self::try_from_bytes) to parse it back.
+	#[cfg(feature = "alloc")]
+	pub fn to_vec(&self) -> alloc::vec::Vec<u8> {
+		self.to_snapshot().to_vec()
+	}
+
+	/// Returns the registers as 256 contiguous dwords, e.g. for
+	/// diffing two register states dword by dword.
+	///
+	/// Each register occupies a 16-byte slot, but only its first
+	/// dword holds meaningful bits, so the register at offset `off`
+	/// is found at index `off / 4`, and the next three dwords are
+	/// reserved.
+	pub fn as_u32_array(&self) -> [u32; 256] {
+		let bytes = self.to_snapshot();
+		core::array::from_fn(|i| RegisterSlots::load(&bytes[i * 4..]))
+	}
+
+	/// Creates the registers from 256 contiguous dwords, as returned
+	/// by [`as_u32_array`](Self::as_u32_array).
+	pub fn from_u32_array(arr: &[u32; 256]) -> Self {
+		let mut bytes = [0; 0x400];
+		arr.store(&mut bytes);
+		Self::from_snapshot(&bytes)
+	}
+}
+
+impl LocalApic {
+	/// Returns the memory image of the whole register page with each
+	/// dword in little-endian order. The hardware format of the
+	/// registers is always little-endian on x86, so this is the same
+	/// as [`to_snapshot`](Self::to_snapshot).
+	#[inline]
+	pub fn to_le_bytes(&self) -> [u8; 0x400] {
+		self.to_snapshot()
+	}
+
+	/// Returns the memory image of the whole register page with each
+	/// dword byte-swapped into big-endian order.
+	pub fn to_be_bytes(&self) -> [u8; 0x400] {
+		let mut out = [0; 0x400];
+		let dwords = self.as_u32_array();
+		for (dword, chunk) in
+			dwords.iter().zip(out.chunks_exact_mut(4))
+		{
+			chunk.copy_from_slice(&dword.to_be_bytes());
+		}
+		out
+	}
+
+	/// Creates the registers from a memory image of the whole
+	/// register page with each dword in little-endian order, as
+	/// returned by [`to_le_bytes`](Self::to_le_bytes).
+	#[inline]
+	pub fn from_le_bytes(bytes: &[u8; 0x400]) -> Self {
+		Self::from_snapshot(bytes)
+	}
+
+	/// Creates the registers from a memory image of the whole
+	/// register page with each dword in big-endian order, as
+	/// returned by [`to_be_bytes`](Self::to_be_bytes).
+	pub fn from_be_bytes(bytes: &[u8; 0x400]) -> Self {
+		let dwords = core::array::from_fn(|i| {
+			let b = &bytes[i * 4..];
+			u32::from_be_bytes([b[0], b[1], b[2], b[3]])
+		});
+		Self::from_u32_array(&dwords)
+	}
+}
+
+impl_snapshot!(
+	__reserved1,
+	apic_id,
+	apic_version,
+	__reserved2,
+	task_priority,
+	arb_priority,
+	processor_priority,
+	eoi,
+	__reserved7,
+	logical_dst,
+	dst_format,
+	spurious_iv,
+	in_service,
+	trigger_mode,
+	interrupt_request,
+	error_status,
+	__reserved8,
+	cmci_lvt,
+	interrupt_cmd_low,
+	interrupt_cmd_high,
+	timer_lvt,
+	thermal_lvt,
+	performance_lvt,
+	lint0_lvt,
+	lint1_lvt,
+	error_lvt,
+	timer_icr,
+	timer_ccr,
+	__reserved9,
+	timer_dcr,
+	__reserved10,
+);
+
+/// Implements raw accessors for fields that are typed as a
+/// [`BitfieldSpecifier`] enum, for callers that need the exact
+/// encoding.
+macro_rules! impl_raw_accessors {
+	($reg:ident {
+		$($field:ident: $ty:ident, $set_field:ident =>
+			$get:ident, $set:ident, $with:ident;)+
+	}) => {
+		impl $reg {
+			$(
+				#[doc = concat!(
+					"Returns the raw encoding of the `",
+					stringify!($field),
+					"` field."
+				)]
+				#[inline]
+				pub fn $get(&self) -> u8 {
+					self.$field() as u8
+				}
+
+				#[doc = concat!(
+					"Sets the raw encoding of the `",
+					stringify!($field),
+					"` field.\n\n# Panics\n\n",
+					"If `bits` is out of bounds for the field."
+				)]
+				#[inline]
+				pub fn $set(&mut self, bits: u8) {
+					let val = <$ty as Specifier>::from_bytes(bits)
+						.expect("value out of bounds for field");
+					self.$set_field(val);
+				}
+
+				#[doc = concat!(
+					"Returns a copy with the raw encoding of the `",
+					stringify!($field),
+					"` field set to `bits`.\n\n# Panics\n\n",
+					"If `bits` is out of bounds for the field."
+				)]
+				#[inline]
+				#[must_use]
+				pub fn $with(mut self, bits: u8) -> Self {
+					self.$set(bits);
+					self
+				}
+			)+
+		}
+	};
+}
+
+impl InterruptCmdLow {
+	/// Builds the low half of the `ICR` for an IPI with the given
+	/// vector, delivery mode and destination shorthand.
+	pub(crate) fn ipi(
+		vector: u8,
+		mode: DeliveryMode,
+		shorthand: DestinationShorthand,
+	) -> Self {
+		Self::new()
+			.with_vector(vector)
+			.with_delivery_mode(mode)
+			.with_level(Level::Assert)
+			.with_shorthand(shorthand)
+	}
+
+	/// Returns whether the last IPI sent through the `ICR` has not
+	/// been accepted yet. Software should wait until this returns
+	/// `false` before sending another IPI.
+	///
+	/// The delivery status is read-only state updated by the
+	/// hardware, so this value is only meaningful when read from the
+	/// actual registers. See [`VolatileApic::wait_for_idle`] for a
+	/// helper that polls it.
+	#[inline]
+	pub fn is_send_pending(&self) -> bool {
+		self.delivery_status() == DeliveryStatus::SendPending
+	}
+}
+
+impl_raw_accessors!(CmciLVT {
+	delivery_mode: DeliveryMode, set_delivery_mode =>
+		delivery_mode_bits, set_delivery_mode_bits,
+		with_delivery_mode_bits;
+});
+impl_raw_accessors!(InterruptCmdLow {
+	delivery_mode: DeliveryMode, set_delivery_mode =>
+		delivery_mode_bits, set_delivery_mode_bits,
+		with_delivery_mode_bits;
+	destination_mode: DestinationMode, set_destination_mode =>
+		destination_mode_bits, set_destination_mode_bits,
+		with_destination_mode_bits;
+	level: Level, set_level =>
+		level_bits, set_level_bits, with_level_bits;
+	trigger: TriggerMode, set_trigger =>
+		trigger_bits, set_trigger_bits, with_trigger_bits;
+	shorthand: DestinationShorthand, set_shorthand =>
+		shorthand_bits, set_shorthand_bits, with_shorthand_bits;
+});
+impl_raw_accessors!(TimerLVT {
+	timer_mode: TimerMode, set_timer_mode =>
+		timer_mode_bits, set_timer_mode_bits, with_timer_mode_bits;
+});
+impl_raw_accessors!(ThermalLVT {
+	delivery_mode: DeliveryMode, set_delivery_mode =>
+		delivery_mode_bits, set_delivery_mode_bits,
+		with_delivery_mode_bits;
+});
+impl_raw_accessors!(PerfLVT {
+	delivery_mode: DeliveryMode, set_delivery_mode =>
+		delivery_mode_bits, set_delivery_mode_bits,
+		with_delivery_mode_bits;
+});
+impl_raw_accessors!(ExtendedLVT {
+	delivery_mode: DeliveryMode, set_delivery_mode =>
+		delivery_mode_bits, set_delivery_mode_bits,
+		with_delivery_mode_bits;
+});
+impl_raw_accessors!(LIntLVT {
+	delivery_mode: DeliveryMode, set_delivery_mode =>
+		delivery_mode_bits, set_delivery_mode_bits,
+		with_delivery_mode_bits;
+	trigger: TriggerMode, set_trigger =>
+		trigger_bits, set_trigger_bits, with_trigger_bits;
+	polarity: PinPolarity, set_polarity =>
+		polarity_bits, set_polarity_bits, with_polarity_bits;
+});
+
+/// Delivery mode for the [`ICR`](LocalApic::interrupt_cmd_low) and
+/// the Local Vector Table entries that support it.
+#[derive(BitfieldSpecifier, Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[bits = 3]
+pub enum DeliveryMode {
+	/// Deliver the interrupt specified in the vector field.
+	Fixed = 0,
+	/// Deliver to the processor with the lowest priority among
+	/// the destination processors.
+	LowestPriority = 1,
+	/// System Management Interrupt.
+	Smi = 2,
+	/// Reserved encoding.
+	Reserved3 = 3,
+	/// Non-Maskable Interrupt.
+	Nmi = 4,
+	/// INIT request.
+	Init = 5,
+	/// Startup IPI (SIPI).
+	Startup = 6,
+	/// External interrupt, signaled by an external controller.
+	ExtInt = 7,
+}
+
+/// Destination shorthand for the
+/// [`ICR`](LocalApic::interrupt_cmd_low).
+#[derive(BitfieldSpecifier, Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[bits = 2]
+pub enum DestinationShorthand {
+	/// The destination is specified in the
+	/// [`ICR` high register](LocalApic::interrupt_cmd_high).
+	NoShorthand = 0,
+	/// The interrupt is sent only to the issuing processor.
+	SelfOnly = 1,
+	/// The interrupt is sent to all processors, including the
+	/// issuing one.
+	AllIncludingSelf = 2,
+	/// The interrupt is sent to all processors, except the issuing
+	/// one.
+	AllExcludingSelf = 3,
+}
+
+/// Destination mode for the
+/// [`ICR`](LocalApic::interrupt_cmd_low).
+#[derive(BitfieldSpecifier, Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[bits = 1]
+pub enum DestinationMode {
+	/// The destination field holds a physical APIC ID.
+	Physical = 0,
+	/// The destination field holds a logical destination, which is
+	/// interpreted according to the
+	/// [`Logical Destination`](LocalApic::logical_dst) and
+	/// [`Destination Format`](LocalApic::dst_format) registers.
+	Logical = 1,
+}
+
+/// Logical destination model for the
+/// [`DFR`](LocalApic::dst_format).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DestinationModel {
+	/// Cluster model (`0b0000`).
+	Cluster,
+	/// Flat model (`0b1111`).
+	Flat,
+}
+
+impl DestinationModel {
+	/// Returns the raw encoding of the model field.
+	pub const fn to_bits(self) -> u8 {
+		match self {
+			Self::Cluster => 0b0000,
+			Self::Flat => 0b1111,
+		}
+	}
+
+	/// Decodes the raw encoding of the model field, returning `None`
+	/// if it is not one of the two defined models.
+	pub const fn from_bits(bits: u8) -> Option<Self> {
+		match bits {
+			0b0000 => Some(Self::Cluster),
+			0b1111 => Some(Self::Flat),
+			_ => None,
+		}
+	}
+}
+
+/// Trigger mode for the [`ICR`](LocalApic::interrupt_cmd_low) and
+/// the [`LINT0`](LocalApic::lint0_lvt) and
+/// [`LINT1`](LocalApic::lint1_lvt) Local Vector Table entries.
+#[derive(BitfieldSpecifier, Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[bits = 1]
+pub enum TriggerMode {
+	/// Edge-triggered interrupt.
+	Edge = 0,
+	/// Level-triggered interrupt.
+	Level = 1,
+}
+
+/// Input pin polarity for the
+/// [`LINT0`](LocalApic::lint0_lvt) and
+/// [`LINT1`](LocalApic::lint1_lvt) Local Vector Table entries.
+#[derive(BitfieldSpecifier, Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[bits = 1]
+pub enum PinPolarity {
+	/// The pin is active when high.
+	ActiveHigh = 0,
+	/// The pin is active when low.
+	ActiveLow = 1,
+}
+
+/// Level for the [`ICR`](LocalApic::interrupt_cmd_low).
+#[derive(BitfieldSpecifier, Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[bits = 1]
+pub enum Level {
+	/// De-assert the interrupt. Only used for the legacy INIT level
+	/// de-assert sequence.
+	Deassert = 0,
+	/// Assert the interrupt.
+	Assert = 1,
+}
+
+/// Delivery status of the [`ICR`](LocalApic::interrupt_cmd_low) and
+/// the Local Vector Table entries.
+#[derive(BitfieldSpecifier, Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[bits = 1]
+pub enum DeliveryStatus {
+	/// There is no activity for this interrupt, or the previous
+	/// interrupt was accepted.
+	Idle = 0,
+	/// The interrupt has been issued but not yet accepted.
+	SendPending = 1,
+}
+
+/// Timer mode for the [`Timer LVT`](LocalApic::timer_lvt).
+#[derive(BitfieldSpecifier, Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[bits = 2]
+pub enum TimerMode {
+	/// The timer counts down once from the initial count.
+	OneShot = 0,
+	/// The timer reloads the initial count when it reaches zero.
+	Periodic = 1,
+	/// The timer fires when the TSC reaches the value written to
+	/// the `IA32_TSC_DEADLINE` MSR.
+	TscDeadline = 2,
+	/// Reserved encoding.
+	Reserved = 3,
+}
+
+/// Divide value for the
+/// [`Timer Divide Configuration Register`](LocalApic::timer_dcr).
+///
+/// The hardware encoding of the divisor is not linear: it is formed
+/// by bits 3, 1 and 0 of the field, while bit 2 is reserved. Use
+/// [`TimerDivisor::to_bits`] and [`TimerDivisor::from_bits`] to
+/// convert to and from the raw encoding.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TimerDivisor {
+	/// Divide by 1.
+	Div1,
+	/// Divide by 2.
+	Div2,
+	/// Divide by 4.
+	Div4,
+	/// Divide by 8.
+	Div8,
+	/// Divide by 16.
+	Div16,
+	/// Divide by 32.
+	Div32,
+	/// Divide by 64.
+	Div64,
+	/// Divide by 128.
+	Div128,
+}
+
+impl TimerDivisor {
+	/// Returns the raw encoding of the divisor, as written to the
+	/// [`TimerDivConf::divisor`] field.
+	pub const fn to_bits(self) -> u8 {
+		match self {
+			Self::Div2 => 0b0000,
+			Self::Div4 => 0b0001,
+			Self::Div8 => 0b0010,
+			Self::Div16 => 0b0011,
+			Self::Div32 => 0b1000,
+			Self::Div64 => 0b1001,
+			Self::Div128 => 0b1010,
+			Self::Div1 => 0b1011,
+		}
+	}
+
+	/// Converts the raw encoding of the divisor into a
+	/// [`TimerDivisor`]. Returns [`None`] if the reserved bit 2 or
+	/// any bit above bit 3 is set.
+	pub const fn from_bits(bits: u8) -> Option<Self> {
+		if bits & !0b1011 != 0 {
+			return None;
+		}
+		Some(Self::decode(bits))
+	}
+
+	/// Decodes bits 3, 1 and 0 of the raw encoding, ignoring the
+	/// rest.
+	const fn decode(bits: u8) -> Self {
+		match bits & 0b1011 {
+			0b0000 => Self::Div2,
+			0b0001 => Self::Div4,
+			0b0010 => Self::Div8,
+			0b0011 => Self::Div16,
+			0b1000 => Self::Div32,
+			0b1001 => Self::Div64,
+			0b1010 => Self::Div128,
+			_ => Self::Div1,
+		}
+	}
+
+	/// Returns the value the timer clock is divided by.
+	pub const fn value(self) -> u32 {
+		match self {
+			Self::Div1 => 1,
+			Self::Div2 => 2,
+			Self::Div4 => 4,
+			Self::Div8 => 8,
+			Self::Div16 => 16,
+			Self::Div32 => 32,
+			Self::Div64 => 64,
+			Self::Div128 => 128,
+		}
+	}
+}
+
+/// An error flagged in the
+/// [`Error Status Register`](LocalApic::error_status). The
+/// discriminant of each variant is its bit in the register.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum ApicError {
+	/// Checksum error on a message sent on the APIC bus.
+	SendChecksum = 0,
+	/// Checksum error on a message received on the APIC bus.
+	ReceiveChecksum = 1,
+	/// A sent message was not accepted by any APIC.
+	SendAccept = 2,
+	/// A received message was not accepted by any APIC.
+	ReceiveAccept = 3,
+	/// An illegal vector was used in a sent message.
+	SendIllegalVector = 5,
+	/// An illegal vector was received or used in an LVT.
+	ReceiveIllegalVector = 6,
+	/// An unimplemented register was accessed.
+	IllegalRegisterAddress = 7,
+}
+
+impl ApicError {
+	/// All the errors, in bit order.
+	const ALL: [Self; 7] = [
+		Self::SendChecksum,
+		Self::ReceiveChecksum,
+		Self::SendAccept,
+		Self::ReceiveAccept,
+		Self::SendIllegalVector,
+		Self::ReceiveIllegalVector,
+		Self::IllegalRegisterAddress,
+	];
+
+	/// Returns a short description of the error.
+	pub const fn message(self) -> &'static str {
+		match self {
+			Self::SendChecksum => "Send Checksum Error",
+			Self::ReceiveChecksum => "Receive Checksum Error",
+			Self::SendAccept => "Send Accept Error",
+			Self::ReceiveAccept => "Receive Accept Error",
+			Self::SendIllegalVector => "Send Illegal Vector",
+			Self::ReceiveIllegalVector => "Received Illegal Vector",
+			Self::IllegalRegisterAddress => {
+				"Illegal Register Address"
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use core::mem;
+	use memoffset::offset_of;
+
+	#[test]
+	fn test_offsets() {
+		// Offsets from Table 16-2. APIC Registers - AMD Architecture
+		// Programmer's Manual Vol. 2: System Programming
+		assert_eq!(offset_of!(LocalApic, apic_id), 0x20);
+		assert_eq!(offset_of!(LocalApic, apic_version), 0x30);
+		assert_eq!(offset_of!(LocalApic, task_priority), 0x80);
+		assert_eq!(offset_of!(LocalApic, arb_priority), 0x90);
+		assert_eq!(offset_of!(LocalApic, processor_priority), 0xa0);
+		assert_eq!(offset_of!(LocalApic, eoi), 0xb0);
+		assert_eq!(offset_of!(LocalApic, logical_dst), 0xd0);
+		assert_eq!(offset_of!(LocalApic, dst_format), 0xe0);
+		assert_eq!(offset_of!(LocalApic, spurious_iv), 0xf0);
+		assert_eq!(offset_of!(LocalApic, in_service), 0x100);
+		assert_eq!(offset_of!(LocalApic, trigger_mode), 0x180);
+		assert_eq!(offset_of!(LocalApic, interrupt_request), 0x200);
+		assert_eq!(offset_of!(LocalApic, error_status), 0x280);
+		assert_eq!(offset_of!(LocalApic, cmci_lvt), 0x2f0);
+		assert_eq!(offset_of!(LocalApic, interrupt_cmd_low), 0x300);
+		assert_eq!(offset_of!(LocalApic, interrupt_cmd_high), 0x310);
+		assert_eq!(offset_of!(LocalApic, timer_lvt), 0x320);
+		assert_eq!(offset_of!(LocalApic, thermal_lvt), 0x330);
+		assert_eq!(offset_of!(LocalApic, performance_lvt), 0x340);
+		assert_eq!(offset_of!(LocalApic, lint0_lvt), 0x350);
+		assert_eq!(offset_of!(LocalApic, lint1_lvt), 0x360);
+		assert_eq!(offset_of!(LocalApic, error_lvt), 0x370);
+		assert_eq!(offset_of!(LocalApic, timer_icr), 0x380);
+		assert_eq!(offset_of!(LocalApic, timer_ccr), 0x390);
+		assert_eq!(offset_of!(LocalApic, timer_dcr), 0x3e0);
+
+		assert_eq!(mem::size_of::<LocalApic>(), 0x400);
+	}
+
+	#[test]
+	fn from_base_ptr() {
+		let mut apic = LocalApic::default();
+		let base = core::ptr::addr_of_mut!(apic).cast::<u8>();
+		assert_eq!(
+			LocalApic::from_base_ptr(base),
+			Ok(&mut apic as *mut _)
+		);
+
+		let misaligned = base.wrapping_add(4);
+		assert_eq!(
+			LocalApic::from_base_ptr(misaligned),
+			Err(AlignmentError(misaligned as usize))
+		);
+	}
+
+	#[test]
+	fn signal_eoi() {
+		let mut apic = LocalApic::default();
+		apic.eoi.set_eoi(0x1234);
+		apic.signal_eoi();
+		assert_eq!(apic.eoi.eoi(), 0);
+	}
+
+	#[test]
+	fn send_ipi() {
+		let mut apic = LocalApic::default();
+		apic.send_ipi(
+			3,
+			0x40,
+			DeliveryMode::Fixed,
+			DestinationShorthand::NoShorthand,
+		);
+		assert_eq!(apic.interrupt_cmd_high.dst(), 3);
+		assert_eq!(apic.interrupt_cmd_low.vector(), 0x40);
+		assert_eq!(
+			apic.interrupt_cmd_low.delivery_mode(),
+			DeliveryMode::Fixed
+		);
+		assert_eq!(
+			apic.interrupt_cmd_low.shorthand(),
+			DestinationShorthand::NoShorthand
+		);
+	}
+
+	#[cfg(feature = "serde")]
+	#[test]
+	fn serde_roundtrip() {
+		let mut apic = LocalApic::default();
+		apic.apic_id.set_apic_id(7);
+		apic.timer_lvt = TimerLVT::default()
+			.with_vector(0x40)
+			.with_timer_mode(TimerMode::Periodic);
+		apic.in_service[2].set_bitfield(0x8000_0001);
+		apic.__reserved10 = Reserved([1, 2, 3, 4]);
+
+		let json = serde_json::to_string(&apic).unwrap();
+		let de: LocalApic = serde_json::from_str(&json).unwrap();
+		assert_eq!(de, apic);
+	}
+
+	#[cfg(feature = "bytemuck")]
+	#[test]
+	fn bytemuck_bytes() {
+		let mut apic = LocalApic::default();
+		apic.apic_id.set_apic_id(7);
+		apic.spurious_iv.set_spurious_vector(0xff);
+		apic.interrupt_request[5].set_bitfield(0xa5a5_a5a5);
+		apic.timer_dcr.set_divide_by(TimerDivisor::Div16);
+
+		let bytes = bytemuck::bytes_of(&apic);
+		assert_eq!(bytes.len(), 0x400);
+
+		let mut expected = [0u8; 0x400];
+		let regs = [
+			(0x20, apic.apic_id.into_bytes()),
+			(0xf0, apic.spurious_iv.into_bytes()),
+			(0x250, apic.interrupt_request[5].into_bytes()),
+			(0x3e0, apic.timer_dcr.into_bytes()),
+		];
+		for (off, reg) in regs {
+			expected[off..off + 16].copy_from_slice(&reg);
+		}
+		assert_eq!(bytes, &expected[..]);
+
+		let copy: &LocalApic = bytemuck::from_bytes(&expected);
+		assert_eq!(*copy, apic);
+	}
+
+	#[cfg(feature = "zerocopy")]
+	#[test]
+	fn zerocopy_bytes() {
+		use zerocopy::{AsBytes, FromBytes};
+
+		#[repr(C, align(16))]
+		struct Page([u8; 0x400]);
+
+		let mut page = Page([0; 0x400]);
+		page.0[0x320..0x324]
+			.copy_from_slice(&0x20040u32.to_le_bytes());
+		let apic = LocalApic::ref_from(&page.0[..]).unwrap();
+		assert_eq!(apic.timer_lvt.vector(), 0x40);
+		assert_eq!(apic.timer_lvt.timer_mode(), TimerMode::Periodic);
+		assert_eq!(apic.as_bytes(), &page.0[..]);
+	}
+
+	#[test]
+	fn const_constructors() {
+		const ICR: InterruptCmdLow =
+			InterruptCmdLow::from_u32(0x4_4030);
+		static TIMER: TimerLVT = TimerLVT::from_u32(0x1_0040);
+		static ZERO: ErrorLVT = ErrorLVT::new();
+
+		assert_eq!(ICR.vector(), 0x30);
+		assert_eq!(ICR.level(), Level::Assert);
+		assert_eq!(ICR.shorthand(), DestinationShorthand::SelfOnly);
+		assert_eq!(TIMER.vector(), 0x40);
+		assert_eq!(TIMER.mask(), 1);
+		assert_eq!(ZERO, ErrorLVT::default());
+	}
+
+	#[test]
+	fn mask_unmask() {
+		fn check<T: MaskableLvt + Default>(mask: impl Fn(&T) -> u8) {
+			let lvt = T::default().masked();
+			assert_eq!(mask(&lvt), 1);
+			let lvt = lvt.unmasked();
+			assert_eq!(mask(&lvt), 0);
+		}
+		check(TimerLVT::mask);
+		check(ThermalLVT::mask);
+		check(PerfLVT::mask);
+		check(LIntLVT::mask);
+		check(ErrorLVT::mask);
+		check(CmciLVT::mask);
+	}
+
+	#[test]
+	fn maskable_lvt_generic() {
+		let mut apic = LocalApic::default();
+		apic.lint0_lvt.set_vector(0x20);
+		mask_all::<dyn MaskableLvt>([
+			&mut apic.timer_lvt as &mut dyn MaskableLvt,
+			&mut apic.thermal_lvt,
+			&mut apic.performance_lvt,
+			&mut apic.lint0_lvt,
+			&mut apic.lint1_lvt,
+			&mut apic.error_lvt,
+		]);
+		assert_eq!(apic.timer_lvt.mask(), 1);
+		assert_eq!(apic.thermal_lvt.mask(), 1);
+		assert_eq!(apic.performance_lvt.mask(), 1);
+		assert_eq!(apic.lint0_lvt.mask(), 1);
+		assert_eq!(apic.lint1_lvt.mask(), 1);
+		assert_eq!(apic.error_lvt.mask(), 1);
+		assert_eq!(MaskableLvt::vector(&apic.lint0_lvt), 0x20);
+		assert!(apic.cmci_lvt.unmasked().masked().is_masked());
+	}
+
+	#[test]
+	fn apic_base() {
+		// xAPIC enabled on the BSP, default base address
+		let base = ApicBase::from(0xfee0_0900u64);
+		assert_eq!(base.bsp(), 1);
+		assert_eq!(base.x2apic_enable(), 0);
+		assert_eq!(base.global_enable(), 1);
+		assert_eq!(base.base_address(), 0xfee0_0000);
+
+		// x2APIC enabled on an AP
+		let base = ApicBase::from(0xfee0_0c00u64);
+		assert_eq!(base.bsp(), 0);
+		assert_eq!(base.x2apic_enable(), 1);
+		assert_eq!(base.global_enable(), 1);
+
+		let base = ApicBase::new()
+			.with_bsp(1)
+			.with_global_enable(1)
+			.with_base_addr(0xfee00);
+		assert_eq!(u64::from(base), 0xfee0_0900);
+
+		// Base address above 64 GiB
+		let base = ApicBase::from(0xf_ffff_fee0_0900u64);
+		assert_eq!(base.base_address(), 0xf_ffff_fee0_0000);
+		assert!(base.base_address() > 1 << 36);
+	}
+
+	#[test]
+	fn send_self_ipi() {
+		let mut apic = LocalApic::default();
+		apic.send_self_ipi(0x55);
+		assert_eq!(apic.interrupt_cmd_low.vector(), 0x55);
+		assert_eq!(
+			apic.interrupt_cmd_low.shorthand(),
+			DestinationShorthand::SelfOnly
+		);
+		assert_eq!(
+			apic.interrupt_cmd_low.delivery_mode(),
+			DeliveryMode::Fixed
+		);
+	}
+
+	#[test]
+	fn max_lvt_entries() {
+		let ver = ApicVersion::default().with_max_lvt(3);
+		assert_eq!(ver.max_lvt_entries(), 4);
+		assert!(!ver.has_perf_lvt());
+		assert!(!ver.has_thermal_lvt());
+		assert!(!ver.has_cmci_lvt());
+
+		let ver = ver.with_max_lvt(4);
+		assert!(ver.has_perf_lvt());
+		assert!(!ver.has_thermal_lvt());
+
+		let ver = ver.with_max_lvt(5);
+		assert!(ver.has_thermal_lvt());
+		assert!(!ver.has_cmci_lvt());
+
+		let ver = ver.with_max_lvt(6);
+		assert_eq!(ver.max_lvt_entries(), 7);
+		assert!(ver.has_cmci_lvt());
+
+		let ver = ver.with_max_lvt(0xff);
+		assert_eq!(ver.max_lvt_entries(), 0xff);
+	}
+
+	#[test]
+	fn try_set_vector() {
+		let mut lvt = TimerLVT::default();
+		assert_eq!(lvt.try_set_vector(15), Err(InvalidVector(15)));
+		assert_eq!(lvt.vector(), 0);
+		assert_eq!(lvt.try_set_vector(16), Ok(()));
+		assert_eq!(lvt.vector(), 16);
+
+		let mut icr = InterruptCmdLow::default();
+		assert_eq!(icr.try_set_vector(0), Err(InvalidVector(0)));
+		assert_eq!(icr.try_set_vector(0xff), Ok(()));
+		assert_eq!(icr.vector(), 0xff);
+	}
+
+	#[test]
+	fn display() {
+		let lvt = TimerLVT::new()
+			.with_vector(0x40)
+			.with_timer_mode(TimerMode::Periodic);
+		assert_eq!(
+			format!("{lvt}"),
+			"TimerLVT { vector: 0x40, delivery_status: Idle, \
+			 mask: 0x0, timer_mode: Periodic }"
+		);
+		let dcr =
+			TimerDivConf::new().with_divide_by(TimerDivisor::Div16);
+		assert_eq!(
+			format!("{dcr}"),
+			"TimerDivConf { divide_by: Div16 }"
+		);
+
+		let mut bitmap = Bitmap256::default();
+		bitmap[1] = BitfieldRegister::from_u32(0x10);
+		assert!(format!("{bitmap}")
+			.starts_with("Bitmap256 [0x00000000, 0x00000010, "));
+
+		let apic = LocalApic {
+			timer_lvt: lvt,
+			..Default::default()
+		};
+		let dump = format!("{apic}");
+		assert!(dump.starts_with("LocalApic {\n\tapic_id: ApicId {"));
+		assert!(dump.contains(&format!("\ttimer_lvt: {lvt},\n")));
+		assert!(dump.ends_with("\n}"));
+	}
+
+	#[test]
+	fn error_status() {
+		let esr = ErrorStatus::new();
+		assert!(esr.is_clear());
+		assert_eq!(esr.errors().count(), 0);
+
+		let esr = ErrorStatus::new()
+			.with_send_illegal_vector(1)
+			.with_illegal_register_addr(1);
+		assert!(!esr.is_clear());
+		assert!(esr.has_error(ApicError::SendIllegalVector));
+		assert!(!esr.has_error(ApicError::SendChecksum));
+		let mut errors = esr.errors();
+		assert_eq!(errors.next(), Some(ApicError::SendIllegalVector));
+		assert_eq!(
+			errors.next(),
+			Some(ApicError::IllegalRegisterAddress)
+		);
+		assert_eq!(errors.next(), None);
+	}
+
+	#[test]
+	fn destination_model() {
+		let dfr = DestinationFormat::new();
+		assert_eq!(
+			dfr.destination_model(),
+			Some(DestinationModel::Cluster)
+		);
+		let dfr = dfr.with_destination_model(DestinationModel::Flat);
+		assert_eq!(dfr.model(), 0b1111);
+		assert_eq!(
+			dfr.destination_model(),
+			Some(DestinationModel::Flat)
+		);
+		let dfr = dfr.with_model(0b0101);
+		assert_eq!(dfr.destination_model(), None);
+	}
+
+	#[test]
+	fn is_send_pending() {
+		let icr = InterruptCmdLow::new();
+		assert!(!icr.is_send_pending());
+		let icr = InterruptCmdLow::from_u32(1 << 12);
+		assert!(icr.is_send_pending());
+		assert_eq!(
+			icr.delivery_status(),
+			DeliveryStatus::SendPending
+		);
+	}
+
+	#[test]
+	fn software_enable() {
+		let siv = SpuriousInterruptVector::new();
+		assert!(!siv.is_enabled());
+		assert!(siv.enable().is_enabled());
+		assert_eq!(siv.enable().disable(), siv);
+
+		let mut apic = LocalApic::default();
+		apic.spurious_iv.set_focus_cpu(1);
+		apic.software_enable(0xff);
+		assert!(apic.spurious_iv.is_enabled());
+		assert_eq!(apic.spurious_iv.spurious_vector(), 0xff);
+		assert_eq!(apic.spurious_iv.focus_cpu(), 1);
+		assert_eq!(
+			SpuriousInterruptVector::from_bytes(
+				apic.spurious_iv.into_bytes()
+			),
+			apic.spurious_iv
+		);
+	}
+
+	#[test]
+	fn focus_processor_checking() {
+		let mut siv = SpuriousInterruptVector::new();
+		assert!(siv.focus_processor_checking_enabled());
+		siv.set_focus_processor_checking(false);
+		assert_eq!(siv.focus_cpu(), 1);
+		assert!(!siv.focus_processor_checking_enabled());
+		siv.set_focus_processor_checking(true);
+		assert_eq!(siv.focus_cpu(), 0);
+	}
+
+	#[test]
+	fn error_display() {
+		fn as_error(e: &dyn core::error::Error) -> String {
+			e.to_string()
+		}
+		assert_eq!(
+			as_error(&AlignmentError(0x1004)),
+			"address 0x1004 is not aligned to 16 bytes"
+		);
+		assert_eq!(
+			as_error(&InvalidVector(2)),
+			"interrupt vector 0x2 is in the reserved 0-15 range"
+		);
+	}
+
+	#[test]
+	fn icr_u64() {
+		let mut apic = LocalApic::default();
+		apic.write_icr(0x0300_0000_0000_4040);
+		assert_eq!(apic.interrupt_cmd_high.dst(), 3);
+		assert_eq!(apic.interrupt_cmd_low.vector(), 0x40);
+		assert_eq!(apic.interrupt_cmd_low.level(), Level::Assert);
+		assert_eq!(apic.read_icr(), 0x0300_0000_0000_4040);
+
+		apic.send_ipi(
+			7,
+			0x30,
+			DeliveryMode::Fixed,
+			DestinationShorthand::NoShorthand,
+		);
+		assert_eq!(apic.read_icr(), 0x0700_0000_0000_4030);
+	}
+
+	#[test]
+	fn delivery_status() {
+		assert_eq!(
+			TimerLVT::new().delivery_status(),
+			DeliveryStatus::Idle
+		);
+		let pending = 1 << 12;
+		assert_eq!(
+			TimerLVT::from_u32(pending).delivery_status(),
+			DeliveryStatus::SendPending
+		);
+		assert_eq!(
+			LIntLVT::from_u32(pending | 0x30).delivery_status(),
+			DeliveryStatus::SendPending
+		);
+		assert_eq!(
+			ErrorLVT::from_u32(0x30).delivery_status(),
+			DeliveryStatus::Idle
+		);
+		assert_eq!(
+			InterruptCmdLow::from_u32(pending).delivery_status(),
+			DeliveryStatus::SendPending
+		);
+	}
+
+	#[test]
+	fn lint_polarity_remote_irr() {
+		let lvt =
+			LIntLVT::new().with_polarity(PinPolarity::ActiveLow);
+		assert_eq!(bytes_to_dword(lvt.into_bytes()), 1 << 13);
+		assert_eq!(lvt.polarity_bits(), 1);
+		assert!(!lvt.remote_irr());
+
+		let lvt = LIntLVT::from_u32(1 << 14);
+		assert!(lvt.remote_irr());
+		assert_eq!(lvt.polarity(), PinPolarity::ActiveHigh);
+	}
+
+	#[test]
+	fn lvt_entries() {
+		let mut apic = LocalApic::default();
+		apic.timer_lvt.set_vector(0x40);
+		apic.error_lvt.set_mask(1);
+		assert_eq!(apic.lvt_entries().count(), 7);
+
+		let (kind, timer) = apic.lvt_entries().nth(1).unwrap();
+		assert_eq!(kind, LvtKind::Timer);
+		assert_eq!(timer.vector(), 0x40);
+		let (kind, error) = apic.lvt_entries().last().unwrap();
+		assert_eq!(kind, LvtKind::Error);
+		assert!(error.is_masked());
+	}
+
+	#[test]
+	fn cluster_logical_dst() {
+		let mut ldr = LogicalDestination::new();
+		ldr.set_cluster(3, 0b0010);
+		assert_eq!(ldr.logical_dst(), 0x32);
+		assert_eq!(ldr.cluster_id(), 3);
+		assert_eq!(ldr.logical_id_mask(), 0b0010);
+		assert_eq!(bytes_to_dword(ldr.into_bytes()), 0x3200_0000);
+	}
+
+	#[test]
+	fn flat_logical_dst() {
+		let mut ldr = LogicalDestination::new();
+		ldr.set_cpu_bit(0);
+		ldr.set_cpu_bit(3);
+		ldr.set_cpu_bit(5);
+		ldr.clear_cpu_bit(5);
+		assert_eq!(ldr.logical_dst(), 0x09);
+		let mut targets = ldr.targets();
+		assert_eq!(targets.next(), Some(0));
+		assert_eq!(targets.next(), Some(3));
+		assert_eq!(targets.next(), None);
+	}
+
+	#[test]
+	fn snapshot() {
+		// Fill the buffer with a simple xorshift PRNG.
+		let mut state = 0x2545_f491_4f6c_dd1du64;
+		let mut buf = [0u8; 0x400];
+		for byte in buf.iter_mut() {
+			state ^= state << 13;
+			state ^= state >> 7;
+			state ^= state << 17;
+			*byte = state as u8;
+		}
+
+		let apic = LocalApic::from_snapshot(&buf);
+		assert_eq!(apic.to_snapshot(), buf);
+		assert_eq!(
+			apic.timer_lvt.into_bytes()[..],
+			buf[0x320..0x330]
+		);
+		assert_eq!(
+			apic.__reserved10.0[3].to_le_bytes()[..],
+			buf[0x3fc..]
+		);
+	}
+
+	#[test]
+	fn configure_timer() {
+		let mut apic = LocalApic::default();
+		apic.timer_lvt.set_mask(1);
+		apic.configure_timer_periodic(
+			0x40,
+			TimerDivisor::Div16,
+			1000,
+		);
+		assert_eq!(apic.timer_dcr.divide_by(), TimerDivisor::Div16);
+		assert_eq!(apic.timer_lvt.vector(), 0x40);
+		assert_eq!(apic.timer_lvt.timer_mode(), TimerMode::Periodic);
+		assert_eq!(apic.timer_lvt.mask(), 0);
+		assert_eq!(apic.timer_icr.count(), 1000);
+
+		apic.configure_timer_one_shot(0x41, TimerDivisor::Div1, 5);
+		assert_eq!(apic.timer_dcr.divide_by(), TimerDivisor::Div1);
+		assert_eq!(apic.timer_lvt.timer_mode(), TimerMode::OneShot);
+		assert_eq!(apic.timer_lvt.vector(), 0x41);
+		assert_eq!(apic.timer_icr.count(), 5);
+	}
+
+	#[test]
+	fn priority_class() {
+		let mut tpr = PriorityRegister::new().with_priority(0x5a);
+		assert_eq!(tpr.priority_class(), 5);
+		assert_eq!(tpr.priority_subclass(), 10);
+		tpr.set_class(2);
+		assert_eq!(tpr.priority(), 0x2a);
+	}
+
+	#[test]
+	fn u32_array() {
+		let mut apic = LocalApic::default();
+		apic.configure_timer_periodic(0x40, TimerDivisor::Div2, 1000);
+		apic.in_service[3] = BitfieldRegister::from_u32(0xdead);
+		let arr = apic.as_u32_array();
+		assert_eq!(arr[0x320 / 4], 0x20040);
+		assert_eq!(arr[0x380 / 4], 1000);
+		assert_eq!(arr[0x130 / 4], 0xdead);
+		assert_eq!(arr.iter().filter(|d| **d != 0).count(), 3);
+		assert_eq!(LocalApic::from_u32_array(&arr), apic);
+	}
+
+	#[test]
+	fn priority_ordering() {
+		let tpr = PriorityRegister::new().with_priority(0x5f);
+		let ppr = PriorityRegister::new().with_priority(0x60);
+		assert!(tpr < ppr);
+		assert!(ppr > tpr);
+		assert_eq!(tpr.max(ppr), ppr);
+		assert_eq!(tpr.cmp(&tpr), core::cmp::Ordering::Equal);
+	}
+
+	#[test]
+	fn hash() {
+		use std::collections::HashSet;
+
+		let mut set = HashSet::new();
+		assert!(set.insert(ApicId::new().with_apic_id(1)));
+		assert!(!set.insert(ApicId::from_u32(1 << 24)));
+		assert!(set.insert(ApicId::new().with_apic_id(2)));
+		assert_eq!(set.len(), 2);
+
+		let mut apics = HashSet::new();
+		apics.insert(LocalApic::default());
+		assert!(apics.contains(&LocalApic::default()));
+	}
+
+	#[test]
+	fn apic_base_flags() {
+		// Enabled BSP in xAPIC mode.
+		let base = ApicBase::from(0xfee0_0900);
+		assert!(base.is_bootstrap_processor());
+		assert!(base.is_global_enabled());
+		assert!(!base.is_x2apic());
+
+		// Enabled AP in x2APIC mode.
+		let base = ApicBase::from(0xfee0_0c00);
+		assert!(!base.is_bootstrap_processor());
+		assert!(base.is_global_enabled());
+		assert!(base.is_x2apic());
+	}
+
+	#[test]
+	fn try_from_bytes() {
+		let mut buf = [0u8; 0x401];
+		buf[0x320] = 0x40;
+		let apic = LocalApic::try_from_bytes(&buf[..0x400]).unwrap();
+		assert_eq!(apic.timer_lvt.vector(), 0x40);
+		assert_eq!(
+			LocalApic::try_from_bytes(&buf[..0x100]),
+			Err(LengthError(0x100))
+		);
+		assert_eq!(
+			LocalApic::try_from_bytes(&buf),
+			Err(LengthError(0x401))
+		);
+		assert_eq!(
+			LengthError(0x100).to_string(),
+			"expected 0x400 bytes, got 0x100"
+		);
+	}
+
+	#[test]
+	fn clear_errors() {
+		let mut apic = LocalApic::default();
+		apic.error_status.set_recv_accept(1);
+		assert!(!apic.error_status.is_clear());
+		apic.clear_errors();
+		assert!(apic.error_status.is_clear());
+	}
+
+	#[test]
+	fn pending_not_in_service() {
+		let mut apic = LocalApic::default();
+		apic.interrupt_request[1] =
+			BitfieldRegister::from_u32(1 << 1);
+		apic.interrupt_request[2] = BitfieldRegister::from_u32(1);
+		apic.in_service[2] = BitfieldRegister::from_u32(1);
+		let mut pending = apic.pending_not_in_service();
+		assert_eq!(pending.next(), Some(33));
+		assert_eq!(pending.next(), None);
+	}
+
+	#[test]
+	fn fluent() {
+		let lvt = ErrorLVT::new().try_with_vector(0x30).unwrap();
+		assert_eq!(lvt.vector(), 0x30);
+		assert_eq!(
+			ErrorLVT::new().try_with_vector(0x3),
+			Err(InvalidVector(0x3))
+		);
+		let siv = SpuriousInterruptVector::new()
+			.with_focus_processor_checking(false);
+		assert_eq!(siv.focus_cpu(), 1);
+		let tpr =
+			PriorityRegister::new().with_priority(0x5a).with_class(6);
+		assert_eq!(tpr.priority(), 0x6a);
+	}
+
+	#[test]
+	fn integrated_apic() {
+		let ver = ApicVersion::new().with_version(0x03);
+		assert!(ver.is_discrete());
+		assert!(!ver.is_integrated());
+		let ver = ApicVersion::new().with_version(0x14);
+		assert!(!ver.is_discrete());
+		assert!(ver.is_integrated());
+		let ver = ApicVersion::new().with_version(0x1f);
+		assert!(!ver.is_discrete());
+		assert!(ver.is_integrated());
+		let ver = ApicVersion::new().with_version(0x20);
+		assert!(!ver.is_discrete());
+		assert!(!ver.is_integrated());
+	}
+
+	#[test]
+	fn endianness() {
+		let apic = LocalApic {
+			timer_lvt: TimerLVT::from_u32(0x20040),
+			..Default::default()
+		};
+		let le = apic.to_le_bytes();
+		assert_eq!(le[0x320..0x324], [0x40, 0x00, 0x02, 0x00]);
+		assert_eq!(LocalApic::from_le_bytes(&le), apic);
+
+		let be = apic.to_be_bytes();
+		assert_eq!(be[0x320..0x324], [0x00, 0x02, 0x00, 0x40]);
+		assert_eq!(LocalApic::from_be_bytes(&be), apic);
+	}
+
+	#[cfg(feature = "alloc")]
+	#[test]
+	fn to_vec() {
+		let mut apic = LocalApic::default();
+		apic.timer_icr.set_count(1000);
+		let bytes = apic.to_vec();
+		assert_eq!(bytes.len(), 0x400);
+		assert_eq!(LocalApic::try_from_bytes(&bytes), Ok(apic));
+	}
+
+	#[test]
+	fn try_send_ipi() {
+		let mut apic = LocalApic::default();
+		assert_eq!(
+			apic.try_send_ipi(
+				3,
+				0x40,
+				DeliveryMode::Fixed,
+				DestinationShorthand::AllExcludingSelf,
+			),
+			Err(IpiConfigError::DestinationWithShorthand(
+				DestinationShorthand::AllExcludingSelf
+			))
+		);
+		assert_eq!(apic.interrupt_cmd_low, InterruptCmdLow::new());
+
+		apic.try_send_ipi(
+			0,
+			0x40,
+			DeliveryMode::Fixed,
+			DestinationShorthand::AllExcludingSelf,
+		)
+		.unwrap();
+		assert_eq!(
+			apic.interrupt_cmd_low.shorthand(),
+			DestinationShorthand::AllExcludingSelf
+		);
+		apic.try_send_ipi(
+			3,
+			0x40,
+			DeliveryMode::Fixed,
+			DestinationShorthand::NoShorthand,
+		)
+		.unwrap();
+		assert_eq!(apic.interrupt_cmd_high.dst(), 3);
+	}
+
+	#[test]
+	fn tsc_deadline() {
+		struct Msr(core::cell::Cell<(u32, u64)>);
+		impl MsrAccess for Msr {
+			fn read_msr(&self, msr: u32) -> u64 {
+				assert_eq!(msr, self.0.get().0);
+				self.0.get().1
+			}
+			fn write_msr(&self, msr: u32, val: u64) {
+				self.0.set((msr, val));
+			}
+		}
+
+		let msr = Msr(core::cell::Cell::new((0, 0)));
+		TscDeadline(0x1234).write(&msr);
+		assert_eq!(msr.0.get(), (0x6e0, 0x1234));
+		assert_eq!(TscDeadline::read(&msr), TscDeadline(0x1234));
+
+		let mut apic = LocalApic::default();
+		apic.configure_timer_tsc_deadline(0x40);
+		assert!(apic.uses_tsc_deadline());
+		assert_eq!(apic.timer_lvt.vector(), 0x40);
+	}
+
+	#[test]
+	fn bitfield_register_bits() {
+		let mut reg = BitfieldRegister::new();
+		reg.set_bit(0, true);
+		reg.set_bit(31, true);
+		assert_eq!(reg.bitfield(), 0x8000_0001);
+		assert!(reg.get_bit(0));
+		assert!(reg.get_bit(31));
+		assert!(!reg.get_bit(1));
+		reg.set_bit(31, false);
+		assert_eq!(reg.bitfield(), 1);
+	}
+
+	#[cfg(debug_assertions)]
+	#[test]
+	#[should_panic(expected = "bit index out of bounds")]
+	fn bitfield_register_out_of_range() {
+		BitfieldRegister::new().get_bit(32);
+	}
+
+	#[test]
+	fn reset_state() {
+		let apic = LocalApic::reset_state();
+		assert_eq!(apic.spurious_iv.spurious_vector(), 0xff);
+		assert!(!apic.spurious_iv.is_enabled());
+		assert_eq!(
+			apic.dst_format.destination_model(),
+			Some(DestinationModel::Flat)
+		);
+		assert!(apic.lvt_entries().all(|(_, lvt)| lvt.is_masked()));
+		assert_eq!(apic.task_priority, PriorityRegister::new());
+	}
+
+	#[test]
+	fn next_serviceable_vector() {
+		let mut apic = LocalApic::default();
+		assert_eq!(apic.next_serviceable_vector(), None);
+
+		// Vector 0x45 (class 4) and 0x31 (class 3) requested.
+		apic.interrupt_request[2] =
+			BitfieldRegister::from_u32(1 << 5);
+		apic.interrupt_request[1] =
+			BitfieldRegister::from_u32(1 << 17);
+		assert_eq!(apic.next_serviceable_vector(), Some(0x45));
+
+		apic.task_priority.set_priority(0x3f);
+		assert_eq!(apic.next_serviceable_vector(), Some(0x45));
+		apic.task_priority.set_priority(0x40);
+		assert_eq!(apic.next_serviceable_vector(), None);
+
+		// An interrupt of class 4 in service also blocks class 4.
+		apic.task_priority.set_priority(0);
+		apic.in_service[2] = BitfieldRegister::from_u32(1 << 2);
+		assert_eq!(apic.next_serviceable_vector(), None);
+		apic.in_service[2] = BitfieldRegister::new();
+		apic.in_service[1] = BitfieldRegister::from_u32(1 << 16);
+		assert_eq!(apic.next_serviceable_vector(), Some(0x45));
+	}
+
+	#[test]
+	fn fully_enabled() {
+		let on = ApicBase::new().with_global_enable(1);
+		let off = ApicBase::new();
+		let siv_on = SpuriousInterruptVector::default().enable();
+		let siv_off = SpuriousInterruptVector::default();
+		assert!(is_fully_enabled(&on, &siv_on));
+		assert!(!is_fully_enabled(&on, &siv_off));
+		assert!(!is_fully_enabled(&off, &siv_on));
+		assert!(!is_fully_enabled(&off, &siv_off));
+	}
+
+	#[test]
+	fn update_lvt() {
+		let mut apic = LocalApic {
+			timer_lvt: TimerLVT::from_u32(1 << 31),
+			..Default::default()
+		};
+		apic.update_lvt(LvtKind::Timer, |lvt: TimerLVT| {
+			lvt.with_vector(0x40)
+		})
+		.unwrap();
+		assert_eq!(apic.timer_lvt.vector(), 0x40);
+		assert_eq!(
+			bytes_to_dword(apic.timer_lvt.into_bytes()),
+			(1 << 31) | 0x40
+		);
+	}
+
+	#[test]
+	fn update_lvt_wrong_type() {
+		let mut apic = LocalApic::default();
+		assert_eq!(
+			apic.update_lvt(LvtKind::Error, |lvt: TimerLVT| lvt),
+			Err(LvtKindMismatch(LvtKind::Error))
+		);
+		apic.update_lvt(LvtKind::Lint1, |lvt: LIntLVT| lvt.masked())
+			.unwrap();
+		assert!(apic.lint1_lvt.is_masked());
+		assert!(!apic.lint0_lvt.is_masked());
+	}
+
+	#[test]
+	fn raw_divisor() {
+		let mut dcr = TimerDivConf::new();
+		assert_eq!(dcr.try_set_raw_divisor(0b1011), Ok(()));
+		assert_eq!(dcr.divide_by(), TimerDivisor::Div1);
+		assert_eq!(
+			dcr.try_set_raw_divisor(0b0100),
+			Err(ReservedBitSet(0b0100))
+		);
+		assert_eq!(
+			dcr.try_set_raw_divisor(0x10),
+			Err(ReservedBitSet(0x10))
+		);
+		assert_eq!(dcr.divisor(), 0b1011);
+	}
+
+	#[test]
+	fn flat_logical_ids() {
+		assert_eq!(physical_to_flat_logical(3), Some(0x08));
+		assert_eq!(physical_to_flat_logical(7), Some(0x80));
+		assert_eq!(physical_to_flat_logical(8), None);
+
+		let ids: Vec<u8> = flat_logical_to_physical(0x0a).collect();
+		assert_eq!(ids, [1, 3]);
+		assert_eq!(flat_logical_to_physical(0).count(), 0);
+	}
+
+	#[test]
+	fn bitmap_dwords() {
+		let mut apic = LocalApic::default();
+		apic.set_in_service_dword(0, 0x8000_0001);
+		apic.set_interrupt_request_dword(7, 0x1234);
+		apic.set_trigger_mode_dword(7, u32::MAX);
+		assert_eq!(apic.in_service_dword(0), 0x8000_0001);
+		assert_eq!(apic.interrupt_request_dword(7), 0x1234);
+		assert_eq!(apic.trigger_mode_dword(7), u32::MAX);
+		assert!(apic.in_service.is_vector_set(31));
+		assert!(apic.interrupt_request.is_vector_set(0xe2));
+		assert_eq!(apic.in_service_dword(7), 0);
+	}
+
+	#[test]
+	#[should_panic]
+	fn bitmap_dword_out_of_bounds() {
+		let apic = LocalApic::default();
+		apic.in_service_dword(8);
+	}
+
+	#[test]
+	fn named_registers() {
+		let mut apic = LocalApic::default();
+		apic.eoi.set_eoi(0x12);
+		apic.set_interrupt_request_dword(7, 0x34);
+		let regs: Vec<_> = apic.named_registers().collect();
+		assert_eq!(regs.len(), 22 + 3 * 8);
+		assert!(regs.contains(&("eoi", 0x12)));
+		assert!(regs.contains(&("interrupt_request[7]", 0x34)));
+		assert_eq!(regs[0].0, "apic_id");
+		assert_eq!(regs.last().unwrap().0, "timer_dcr");
+	}
+
+	#[test]
+	fn try_set_tsc_deadline() {
+		let mut lvt = TimerLVT::new();
+		assert_eq!(
+			lvt.try_set_tsc_deadline(false),
+			Err(UnsupportedTimerMode(TimerMode::TscDeadline))
+		);
+		assert_eq!(lvt.timer_mode(), TimerMode::OneShot);
+		assert_eq!(lvt.try_set_tsc_deadline(true), Ok(()));
+		assert_eq!(lvt.timer_mode(), TimerMode::TscDeadline);
+	}
+
+	#[test]
+	fn copy_priority() {
+		let mut apic = LocalApic::default();
+		apic.task_priority.set_priority(0x35);
+		let mut shadow = PriorityRegister::from_u32(1 << 31);
+		super::copy_priority(&apic.task_priority, &mut shadow);
+		assert_eq!(shadow.priority(), 0x35);
+		assert_eq!(
+			bytes_to_dword(shadow.into_bytes()),
+			(1 << 31) | 0x35
+		);
+	}
+
+	#[test]
+	fn error_messages() {
+		let esr = ErrorStatus::new()
+			.with_recv_illegal_vector(1)
+			.with_send_cs(1);
+		let mut messages = esr.messages();
+		assert_eq!(messages.next(), Some("Send Checksum Error"));
+		assert_eq!(messages.next(), Some("Received Illegal Vector"));
+		assert_eq!(messages.next(), None);
+	}
+
+	#[test]
+	fn lvt_enum() {
+		let lvts = [
+			Lvt::Cmci(CmciLVT::new().with_vector(0x20)),
+			Lvt::Timer(TimerLVT::new().with_vector(0x21)),
+			Lvt::Thermal(ThermalLVT::new().with_vector(0x22)),
+			Lvt::Performance(PerfLVT::new().with_vector(0x23)),
+			Lvt::Lint0(LIntLVT::new().with_vector(0x24)),
+			Lvt::Lint1(LIntLVT::new().with_vector(0x25).masked()),
+			Lvt::Error(ErrorLVT::new().with_vector(0x26)),
+		];
+		let apic = LocalApic::default();
+		for ((lvt, (kind, _)), v) in
+			lvts.iter().zip(apic.lvt_entries()).zip(0x20..)
+		{
+			assert_eq!(lvt.kind(), kind);
+			assert_eq!(lvt.vector(), v);
+			assert_eq!(lvt.is_masked(), kind == LvtKind::Lint1);
+		}
+	}
+
+	#[test]
+	fn timer_progress() {
+		let mut apic = LocalApic::default();
+		assert_eq!(apic.timer_progress(), None);
+		assert_eq!(apic.timer_fraction_remaining(), None);
+
+		apic.timer_icr.set_count(1000);
+		apic.timer_ccr.set_count(250);
+		assert_eq!(apic.timer_progress(), Some((250, 1000)));
+		assert_eq!(apic.timer_fraction_remaining(), Some(0.25));
+
+		apic.timer_ccr.set_count(2000);
+		assert_eq!(apic.timer_progress(), Some((2000, 1000)));
+		assert_eq!(apic.timer_fraction_remaining(), Some(1.0));
+	}
+
+	#[test]
+	fn overlay() {
+		let mut lvt = TimerLVT::from_u32(1 << 31)
+			.with_vector(0x20)
+			.with_timer_mode(TimerMode::Periodic)
+			.masked();
+		lvt.overlay(
+			TimerLVT::from_u32(u32::MAX).with_vector(0x40),
+			0xff,
+		);
+		assert_eq!(lvt.vector(), 0x40);
+		assert_eq!(lvt.timer_mode(), TimerMode::Periodic);
+		assert!(lvt.is_masked());
+		assert_eq!(bytes_to_dword(lvt.into_bytes()), 0x8003_0040);
+	}
+
+	#[test]
+	fn eoi_broadcast_suppression() {
+		let siv = SpuriousInterruptVector::from_u32(1 << 12);
+		assert!(siv.is_eoi_broadcast_suppressed());
+		assert_eq!(siv.spurious_vector(), 0);
+		assert!(!siv.is_enabled());
+
+		let siv = SpuriousInterruptVector::new()
+			.with_eoi_broadcast_suppression(1);
+		assert_eq!(bytes_to_dword(siv.into_bytes()), 1 << 12);
+		assert!(!SpuriousInterruptVector::from_u32(0x3ff)
+			.is_eoi_broadcast_suppressed());
+	}
+
+	#[test]
+	fn eoi_suppression_support() {
+		let ver = ApicVersion::from_u32(1 << 24);
+		assert!(ver.supports_eoi_suppression());
+		assert_eq!(ver.max_lvt(), 0);
+		assert!(!ApicVersion::from_u32(0x00ff_ffff)
+			.supports_eoi_suppression());
+		let ver = ApicVersion::new()
+			.with_supports_eoi_broadcast_suppression(1);
+		assert_eq!(bytes_to_dword(ver.into_bytes()), 1 << 24);
+	}
+
+	#[test]
+	fn mask_all_lvts() {
+		let mut apic = LocalApic::default();
+		apic.mask_all_lvts();
+		assert!(apic.lvt_entries().all(|(_, lvt)| lvt.is_masked()));
+		apic.unmask_all_lvts();
+		assert!(apic.lvt_entries().all(|(_, lvt)| !lvt.is_masked()));
+	}
+
+	#[test]
+	fn validate() {
+		assert_eq!(LocalApic::reset_state().validate(), Ok(()));
+		assert_eq!(
+			LocalApic::default().validate(),
+			Err(ApicConfigError::IllegalVector(LvtKind::Cmci, 0))
+		);
+
+		let mut apic = LocalApic::reset_state();
+		apic.configure_timer_periodic(0x40, TimerDivisor::Div2, 100);
+		apic.lint0_lvt.set_delivery_mode(DeliveryMode::ExtInt);
+		assert_eq!(apic.validate(), Ok(()));
+
+		let mut bad = apic;
+		bad.dst_format.set_model(0x5);
+		assert_eq!(
+			bad.validate(),
+			Err(ApicConfigError::InvalidDestinationModel(0x5))
+		);
+
+		let mut bad = apic;
+		bad.lint1_lvt.set_delivery_mode(DeliveryMode::Reserved3);
+		assert_eq!(
+			bad.validate(),
+			Err(ApicConfigError::ReservedDeliveryMode(
+				LvtKind::Lint1
+			))
+		);
+
+		let mut bad = apic;
+		bad.timer_lvt.set_vector(0x0f);
+		assert_eq!(
+			bad.validate(),
+			Err(ApicConfigError::IllegalVector(LvtKind::Timer, 0x0f))
+		);
+
+		let mut bad = apic;
+		bad.timer_lvt.set_timer_mode(TimerMode::Reserved);
+		assert_eq!(
+			bad.validate(),
+			Err(ApicConfigError::ReservedTimerMode)
+		);
+
+		let mut bad = apic;
+		bad.timer_dcr.set_divisor(0b0100);
+		assert_eq!(
+			bad.validate(),
+			Err(ApicConfigError::ReservedDivisor(0b0100))
+		);
+	}
+
+	#[test]
+	fn try_set_delivery_mode() {
+		let mut lint = LIntLVT::new();
+		assert_eq!(
+			lint.try_set_delivery_mode(DeliveryMode::ExtInt),
+			Ok(())
+		);
+		assert_eq!(lint.delivery_mode(), DeliveryMode::ExtInt);
+
+		let mut thermal = ThermalLVT::new();
+		assert_eq!(
+			thermal.try_set_delivery_mode(DeliveryMode::ExtInt),
+			Err(IllegalDeliveryMode(DeliveryMode::ExtInt))
+		);
+		assert_eq!(
+			thermal.try_set_delivery_mode(DeliveryMode::Startup),
+			Err(IllegalDeliveryMode(DeliveryMode::Startup))
+		);
+		assert_eq!(thermal.delivery_mode(), DeliveryMode::Fixed);
+		assert_eq!(
+			thermal.try_set_delivery_mode(DeliveryMode::Nmi),
+			Ok(())
+		);
+		assert_eq!(thermal.delivery_mode(), DeliveryMode::Nmi);
+	}
+
+	#[test]
+	fn apic_id_id_width() {
+		assert_eq!(ApicId::id_width(), 8);
+		let id = ApicId::new().with_apic_id(0xff);
+		assert_eq!(bytes_to_dword(id.into_bytes()), 0xff00_0000);
+		assert_eq!(ApicId::from_u32(0x0f00_0000).apic_id(), 0x0f);
+	}
+
+	#[test]
+	fn from_byte_array() {
+		let mut bytes = [0; 16];
+		bytes[3] = 0x07;
+		bytes[15] = 0xaa;
+		let id = ApicId::from(bytes);
+		assert_eq!(id.apic_id(), 0x07);
+		assert_eq!(id.into_bytes(), bytes);
+
+		let lvt = TimerLVT::new().with_vector(0x40).masked();
+		let raw: [u8; 16] = lvt.into();
+		assert_eq!(raw[..4], [0x40, 0, 1, 0]);
+		assert_eq!(TimerLVT::from(raw), lvt);
+	}
+
+	#[test]
+	fn as_u32() {
+		assert_eq!(
+			TimerLVT::default().with_vector(0x40).as_u32(),
+			0x40
+		);
+		let mut bytes = [0xff; 16];
+		bytes[..4].copy_from_slice(&0x1234u32.to_le_bytes());
+		assert_eq!(TimerCount::from(bytes).as_u32(), 0x1234);
+	}
+
+	#[test]
+	fn from_u32() {
+		let lvt = TimerLVT::from_u32(0x20040);
+		assert_eq!(lvt.vector(), 0x40);
+		assert_eq!(lvt.timer_mode(), TimerMode::Periodic);
+		assert!(!lvt.is_masked());
+		assert_eq!(lvt.into_bytes()[4..], [0; 12]);
+
+		fn roundtrip<T: ApicRegister32>(val: u32) -> u32 {
+			T::from_u32(val).as_u32()
+		}
+		assert_eq!(roundtrip::<TimerLVT>(0x20040), 0x20040);
+		assert_eq!(roundtrip::<TimerCount>(u32::MAX), u32::MAX);
+	}
+
+	#[test]
+	fn handle_eoi() {
+		let mut apic = LocalApic::default();
+		assert_eq!(apic.handle_eoi(), None);
+
+		apic.in_service.set_vector(0x31);
+		apic.in_service.set_vector(0x80);
+		apic.trigger_mode.set_vector(0x80);
+		assert_eq!(
+			apic.handle_eoi(),
+			Some(EoiEffect {
+				vector: 0x80,
+				level_triggered: true,
+				broadcast: true,
+			})
+		);
+		assert!(!apic.in_service.is_vector_set(0x80));
+		assert!(apic.trigger_mode.is_vector_set(0x80));
+
+		apic.in_service.set_vector(0x80);
+		apic.spurious_iv.set_eoi_broadcast_suppression(1);
+		assert_eq!(
+			apic.handle_eoi(),
+			Some(EoiEffect {
+				vector: 0x80,
+				level_triggered: true,
+				broadcast: false,
+			})
+		);
+
+		assert_eq!(
+			apic.handle_eoi(),
+			Some(EoiEffect {
+				vector: 0x31,
+				level_triggered: false,
+				broadcast: false,
+			})
+		);
+		assert_eq!(apic.in_service, Bitmap256::default());
+	}
+
+	#[test]
+	fn interrupt_mask_level() {
+		let mut apic = LocalApic::default();
+		apic.task_priority.set_priority(0x2a);
+		apic.set_interrupt_mask_level(0x47);
+		assert_eq!(apic.task_priority.priority_class(), 4);
+		assert_eq!(apic.task_priority.priority(), 0x40);
+
+		apic.interrupt_request.set_vector(0x4f);
+		assert_eq!(apic.next_serviceable_vector(), None);
+		apic.interrupt_request.set_vector(0x50);
+		assert_eq!(apic.next_serviceable_vector(), Some(0x50));
+	}
+
+	#[test]
+	fn set_field() {
+		let id = ApicId::default().with_apic_id(3);
+		let bytes = id.into_bytes()[..4].try_into().unwrap();
+		let val = u32::from_le_bytes(bytes);
+		assert_eq!(val, 0x3000000);
+	}
+
+	#[test]
+	fn apic_id_width() {
+		let id = ApicId::default().with_apic_id(0xF3);
+		let bytes = id.into_bytes()[..4].try_into().unwrap();
+		let val = u32::from_le_bytes(bytes);
+		assert_eq!(val, 0xF3000000);
+		assert_eq!(id.apic_id(), 0xF3);
+	}
+
+	#[test]
+	fn delivery_mode() {
+		let icr = InterruptCmdLow::default()
+			.with_delivery_mode(DeliveryMode::Startup);
+		assert_eq!(icr.delivery_mode_bits(), 6);
+		assert_eq!(icr.into_bytes()[1], 0x6);
+
+		let lint = LIntLVT::default().with_delivery_mode_bits(7);
+		assert_eq!(lint.delivery_mode(), DeliveryMode::ExtInt);
+	}
+
+	#[test]
+	fn timer_mode() {
+		for (mode, val) in [
+			(TimerMode::OneShot, 0b00),
+			(TimerMode::Periodic, 0b01),
+			(TimerMode::TscDeadline, 0b10),
+			(TimerMode::Reserved, 0b11),
+		] {
+			let lvt = TimerLVT::default().with_timer_mode(mode);
+			let bytes = lvt.into_bytes();
+			let raw =
+				u32::from_le_bytes(bytes[..4].try_into().unwrap());
+			assert_eq!(raw, val << 17);
+			let lvt = TimerLVT::from_bytes(bytes);
+			assert_eq!(lvt.timer_mode(), mode);
+		}
+	}
+
+	#[test]
+	fn timer_divisor() {
+		// Bits 3, 1 and 0 of the Timer Divide Configuration Register
+		for (div, bits, value) in [
+			(TimerDivisor::Div2, 0b0000, 2),
+			(TimerDivisor::Div4, 0b0001, 4),
+			(TimerDivisor::Div8, 0b0010, 8),
+			(TimerDivisor::Div16, 0b0011, 16),
+			(TimerDivisor::Div32, 0b1000, 32),
+			(TimerDivisor::Div64, 0b1001, 64),
+			(TimerDivisor::Div128, 0b1010, 128),
+			(TimerDivisor::Div1, 0b1011, 1),
+		] {
+			assert_eq!(div.to_bits(), bits);
+			assert_eq!(div.value(), value);
+			assert_eq!(TimerDivisor::from_bits(bits), Some(div));
+
+			let dcr = TimerDivConf::default().with_divide_by(div);
+			assert_eq!(dcr.divisor(), bits);
+			assert_eq!(dcr.divide_by(), div);
+		}
+		assert_eq!(TimerDivisor::from_bits(0b0100), None);
+		assert_eq!(TimerDivisor::from_bits(0b10000), None);
+	}
+
+	#[test]
+	fn destination_mode() {
+		let icr = InterruptCmdLow::default()
+			.with_destination_mode(DestinationMode::Logical);
+		assert_eq!(icr.into_bytes()[1], 1 << 3);
+		assert_eq!(icr.destination_mode_bits(), 1);
+		let icr = icr.with_destination_mode_bits(0);
+		assert_eq!(icr.destination_mode(), DestinationMode::Physical);
+	}
+
+	#[test]
+	fn trigger_mode() {
+		let icr = InterruptCmdLow::default()
+			.with_trigger(TriggerMode::Level);
+		assert_eq!(icr.into_bytes()[1], 1 << 7);
+		let icr = InterruptCmdLow::from_bytes(icr.into_bytes());
+		assert_eq!(icr.trigger(), TriggerMode::Level);
+
+		let lint =
+			LIntLVT::default().with_trigger(TriggerMode::Level);
+		assert_eq!(lint.into_bytes()[1], 1 << 7);
+		let lint = LIntLVT::from_bytes(lint.into_bytes());
+		assert_eq!(lint.trigger(), TriggerMode::Level);
+		assert_eq!(
+			lint.with_trigger_bits(0).trigger(),
+			TriggerMode::Edge
+		);
+	}
+
+	#[test]
+	fn level() {
+		let icr =
+			InterruptCmdLow::default().with_level(Level::Assert);
+		assert_eq!(icr.into_bytes()[1], 1 << 6);
+		assert_eq!(icr.level_bits(), 1);
+		let icr = icr.with_level(Level::Deassert);
+		assert_eq!(icr.into_bytes()[1], 0);
+	}
+
+	#[test]
+	fn destination_shorthand() {
+		// Bits 19:18 of the ICR
+		for (sh, val) in [
+			(DestinationShorthand::NoShorthand, 0b00),
+			(DestinationShorthand::SelfOnly, 0b01),
+			(DestinationShorthand::AllIncludingSelf, 0b10),
+			(DestinationShorthand::AllExcludingSelf, 0b11),
+		] {
+			let icr = InterruptCmdLow::default().with_shorthand(sh);
+			let bytes = icr.into_bytes()[..4].try_into().unwrap();
+			assert_eq!(u32::from_le_bytes(bytes), val << 18);
+			assert_eq!(icr.shorthand_bits(), val as u8);
+		}
 	}
 }
@@ -0,0 +1,139 @@
+//! Volatile accesses to single registers of a memory-mapped
+//! [`LocalApic`] through a raw pointer.
+//!
+//! These functions are useful when only one register needs to be
+//! accessed, like writing the EOI register at the end of an
+//! interrupt handler, without keeping a [`VolatileApic`] around.
+//! Like its accessors, each function performs a single volatile
+//! access to the first dword of the register.
+//!
+//! ```
+//! # use lapic::{mmio, LocalApic};
+//! # let mut apic = LocalApic::default();
+//! # let base: *mut LocalApic = &mut apic;
+//! // SAFETY: `base` points to the local APIC registers.
+//! unsafe { mmio::signal_eoi(base) };
+//! ```
+#![allow(unsafe_code)]
+
+use crate::*;
+use core::ptr;
+
+/// Generates functions performing a volatile read of a register.
+macro_rules! mmio_read {
+	($($(#[$attr:meta])* $field:ident: $ty:ty => $read:ident;)+) => {
+		$(
+			$(#[$attr])*
+			///
+			/// # Safety
+			///
+			/// `base` must be non-null, aligned to 16 bytes, and point
+			/// to the mapped local APIC register page, valid for
+			/// reads.
+			#[inline]
+			pub unsafe fn $read(base: *const LocalApic) -> $ty {
+				// SAFETY: guaranteed by the caller.
+				let val = unsafe {
+					let reg = ptr::addr_of!((*base).$field);
+					ptr::read_volatile(reg.cast::<u32>())
+				};
+				<$ty>::from_u32(val)
+			}
+		)+
+	};
+}
+
+/// Generates functions performing a volatile write of a register.
+macro_rules! mmio_write {
+	($($(#[$attr:meta])* $field:ident: $ty:ty => $write:ident;)+) => {
+		$(
+			$(#[$attr])*
+			///
+			/// # Safety
+			///
+			/// `base` must be non-null, aligned to 16 bytes, and point
+			/// to the mapped local APIC register page, valid for
+			/// writes.
+			#[inline]
+			pub unsafe fn $write(base: *mut LocalApic, val: $ty) {
+				let val = bytes_to_dword(val.into_bytes());
+				// SAFETY: guaranteed by the caller.
+				unsafe {
+					let reg = ptr::addr_of_mut!((*base).$field);
+					ptr::write_volatile(reg.cast::<u32>(), val);
+				}
+			}
+		)+
+	};
+}
+
+/// Signals the end of the current interrupt by writing zero to the
+/// [`EOI`](LocalApic::eoi) register.
+///
+/// # Safety
+///
+/// `base` must be non-null, aligned to 16 bytes, and point to the
+/// mapped local APIC register page, valid for writes.
+#[inline]
+pub unsafe fn signal_eoi(base: *mut LocalApic) {
+	// SAFETY: guaranteed by the caller.
+	unsafe { write_eoi(base, EndOfInterrupt::default()) };
+}
+
+mmio_read! {
+	/// Reads the APIC ID Register.
+	apic_id: ApicId => read_apic_id;
+	/// Reads the APIC Version Register.
+	apic_version: ApicVersion => read_apic_version;
+	/// Reads the Task Priority Register (TPR).
+	task_priority: PriorityRegister => read_task_priority;
+	/// Reads the Processor Priority Register (PPR).
+	processor_priority: PriorityRegister => read_processor_priority;
+	/// Reads the Spurious Interrupt Vector Register.
+	spurious_iv: SpuriousInterruptVector => read_spurious_iv;
+	/// Reads the Interrupt Command Register Low (bits 31:0).
+	interrupt_cmd_low: InterruptCmdLow => read_interrupt_cmd_low;
+	/// Reads the Timer Local Vector Table Entry.
+	timer_lvt: TimerLVT => read_timer_lvt;
+	/// Reads the Timer Current Count Register.
+	timer_ccr: TimerCount => read_timer_ccr;
+}
+
+mmio_write! {
+	/// Writes the Task Priority Register (TPR).
+	task_priority: PriorityRegister => write_task_priority;
+	/// Writes the End of Interrupt Register (EOI).
+	eoi: EndOfInterrupt => write_eoi;
+	/// Writes the Spurious Interrupt Vector Register.
+	spurious_iv: SpuriousInterruptVector => write_spurious_iv;
+	/// Writes the Interrupt Command Register Low (bits 31:0).
+	/// This write triggers the delivery of the interrupt.
+	interrupt_cmd_low: InterruptCmdLow => write_interrupt_cmd_low;
+	/// Writes the Interrupt Command Register High (bits 63:32).
+	interrupt_cmd_high: InterruptCmdHigh => write_interrupt_cmd_high;
+	/// Writes the Timer Local Vector Table Entry.
+	timer_lvt: TimerLVT => write_timer_lvt;
+	/// Writes the Timer Initial Count Register.
+	timer_icr: TimerCount => write_timer_icr;
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn read_write() {
+		let mut apic = LocalApic::default();
+		apic.apic_id.set_apic_id(3);
+		apic.eoi.set_eoi(0xff);
+		let base: *mut LocalApic = &mut apic;
+		unsafe {
+			assert_eq!(read_apic_id(base).apic_id(), 3);
+			write_timer_lvt(base, TimerLVT::new().with_vector(0x40));
+			assert_eq!(read_timer_lvt(base).vector(), 0x40);
+			signal_eoi(base);
+		}
+		assert_eq!(apic.eoi, EndOfInterrupt::default());
+		assert_eq!(apic.timer_lvt.vector(), 0x40);
+	}
+}
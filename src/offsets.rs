@@ -0,0 +1,546 @@
+//! Offsets of the memory-mapped local APIC registers.
+
+use crate::*;
+
+/// A register type that is located at a single offset in
+/// [`LocalApic`].
+///
+/// Types used for several registers, like [`PriorityRegister`],
+/// [`LIntLVT`], [`TimerCount`] and [`BitfieldRegister`], do not
+/// implement this trait; use [`ApicRegisterOffset`] for them
+/// instead.
+///
+/// ```
+/// # use lapic::{ApicRegister, SpuriousInterruptVector};
+/// assert_eq!(SpuriousInterruptVector::OFFSET, 0xf0);
+/// ```
+pub trait ApicRegister {
+	/// Offset of the register from the base of the register page.
+	const OFFSET: u16;
+}
+
+/// Implements [`ApicRegister`] for the given register types.
+macro_rules! impl_apic_register {
+	($($ty:ident => $reg:ident,)+) => {
+		$(
+			impl ApicRegister for $ty {
+				const OFFSET: u16 = ApicRegisterOffset::$reg.offset();
+			}
+		)+
+	};
+}
+
+impl_apic_register! {
+	ApicId => ApicId,
+	ApicVersion => Version,
+	EndOfInterrupt => Eoi,
+	LogicalDestination => LogicalDestination,
+	DestinationFormat => DestinationFormat,
+	SpuriousInterruptVector => SpuriousInterruptVector,
+	ErrorStatus => ErrorStatus,
+	CmciLVT => CmciLvt,
+	InterruptCmdLow => InterruptCommandLow,
+	InterruptCmdHigh => InterruptCommandHigh,
+	TimerLVT => TimerLvt,
+	ThermalLVT => ThermalLvt,
+	PerfLVT => PerformanceLvt,
+	ErrorLVT => ErrorLvt,
+	TimerDivConf => TimerDivideConfig,
+}
+
+/// Offsets of the local APIC registers from the base of the register
+/// page, as laid out in [`LocalApic`].
+#[repr(u16)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ApicRegisterOffset {
+	/// APIC ID Register.
+	ApicId = 0x20,
+	/// APIC Version Register.
+	Version = 0x30,
+	/// Task Priority Register (TPR).
+	TaskPriority = 0x80,
+	/// Arbitration Priority Register (APR).
+	ArbitrationPriority = 0x90,
+	/// Processor Priority Register (PPR).
+	ProcessorPriority = 0xa0,
+	/// End of Interrupt Register (EOI).
+	Eoi = 0xb0,
+	/// Logical Destination Register.
+	LogicalDestination = 0xd0,
+	/// Destination Format Register.
+	DestinationFormat = 0xe0,
+	/// Spurious Interrupt Vector Register.
+	SpuriousInterruptVector = 0xf0,
+	/// First of the 8 In-Service Registers (ISR).
+	InService = 0x100,
+	/// First of the 8 Trigger Mode Registers (TMR).
+	TriggerMode = 0x180,
+	/// First of the 8 Interrupt Request Registers (IRR).
+	InterruptRequest = 0x200,
+	/// Error Status Register (ESR).
+	ErrorStatus = 0x280,
+	/// CMCI Local Vector Table Entry.
+	CmciLvt = 0x2f0,
+	/// Interrupt Command Register Low (bits 31:0).
+	InterruptCommandLow = 0x300,
+	/// Interrupt Command Register High (bits 63:32).
+	InterruptCommandHigh = 0x310,
+	/// Timer Local Vector Table Entry.
+	TimerLvt = 0x320,
+	/// Thermal Local Vector Table Entry.
+	ThermalLvt = 0x330,
+	/// Performance Counter Local Vector Table Entry.
+	PerformanceLvt = 0x340,
+	/// Local Interrupt 0 Vector Table Entry.
+	Lint0Lvt = 0x350,
+	/// Local Interrupt 1 Vector Table Entry.
+	Lint1Lvt = 0x360,
+	/// Error Vector Table Entry.
+	ErrorLvt = 0x370,
+	/// Timer Initial Count Register.
+	TimerInitialCount = 0x380,
+	/// Timer Current Count Register.
+	TimerCurrentCount = 0x390,
+	/// Timer Divide Configuration Register.
+	TimerDivideConfig = 0x3e0,
+}
+
+impl ApicRegisterOffset {
+	/// Returns the offset of the register.
+	#[inline]
+	pub const fn offset(self) -> u16 {
+		self as u16
+	}
+
+	/// Returns the register that an access at offset `off` hits, or
+	/// `None` if the offset falls in a reserved region.
+	///
+	/// Any offset within the 16-byte slot of a register maps to that
+	/// register, and any offset within the 8 slots of the bitmap
+	/// registers maps to the corresponding bitmap register.
+	pub const fn from_offset(off: u16) -> Option<Self> {
+		let reg = match off & !0xf {
+			0x20 => Self::ApicId,
+			0x30 => Self::Version,
+			0x80 => Self::TaskPriority,
+			0x90 => Self::ArbitrationPriority,
+			0xa0 => Self::ProcessorPriority,
+			0xb0 => Self::Eoi,
+			0xd0 => Self::LogicalDestination,
+			0xe0 => Self::DestinationFormat,
+			0xf0 => Self::SpuriousInterruptVector,
+			0x100..=0x170 => Self::InService,
+			0x180..=0x1f0 => Self::TriggerMode,
+			0x200..=0x270 => Self::InterruptRequest,
+			0x280 => Self::ErrorStatus,
+			0x2f0 => Self::CmciLvt,
+			0x300 => Self::InterruptCommandLow,
+			0x310 => Self::InterruptCommandHigh,
+			0x320 => Self::TimerLvt,
+			0x330 => Self::ThermalLvt,
+			0x340 => Self::PerformanceLvt,
+			0x350 => Self::Lint0Lvt,
+			0x360 => Self::Lint1Lvt,
+			0x370 => Self::ErrorLvt,
+			0x380 => Self::TimerInitialCount,
+			0x390 => Self::TimerCurrentCount,
+			0x3e0 => Self::TimerDivideConfig,
+			_ => return None,
+		};
+		Some(reg)
+	}
+}
+
+/// A decoded access to the memory-mapped local APIC registers, as
+/// returned by [`LocalApic::decode_access`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ApicAccess {
+	/// An access to a register other than the bitmap registers.
+	Register(ApicRegisterOffset),
+	/// An access to the given dword (0-7) of the
+	/// [`In-Service Register`](LocalApic::in_service).
+	InService(usize),
+	/// An access to the given dword (0-7) of the
+	/// [`Trigger Mode Register`](LocalApic::trigger_mode).
+	TriggerMode(usize),
+	/// An access to the given dword (0-7) of the
+	/// [`Interrupt Request Register`](LocalApic::interrupt_request).
+	InterruptRequest(usize),
+}
+
+impl LocalApic {
+	/// Decodes an access at offset `offset` of the register page
+	/// into the register it hits and, for the bitmap registers, the
+	/// index of the dword within the register. Returns `None` if the
+	/// offset falls in a reserved region.
+	///
+	/// ```
+	/// # use lapic::{ApicAccess, LocalApic};
+	/// assert_eq!(
+	///     LocalApic::decode_access(0x130),
+	///     Some(ApicAccess::InService(3))
+	/// );
+	/// ```
+	pub const fn decode_access(offset: u16) -> Option<ApicAccess> {
+		let Some(reg) = ApicRegisterOffset::from_offset(offset)
+		else {
+			return None;
+		};
+		let index = ((offset - reg.offset()) >> 4) as usize;
+		let access = match reg {
+			ApicRegisterOffset::InService => {
+				ApicAccess::InService(index)
+			}
+			ApicRegisterOffset::TriggerMode => {
+				ApicAccess::TriggerMode(index)
+			}
+			ApicRegisterOffset::InterruptRequest => {
+				ApicAccess::InterruptRequest(index)
+			}
+			reg => ApicAccess::Register(reg),
+		};
+		Some(access)
+	}
+
+	/// Reads the dword at offset `offset` of the register page, as
+	/// an emulated local APIC would.
+	///
+	/// Returns `None` if the access is illegal, in which case an
+	/// emulator should set the
+	/// [`illegal_register_addr`](ErrorStatus::illegal_register_addr)
+	/// bit of the ESR. This is the case for offsets in reserved
+	/// regions, offsets that are not the first dword of a register,
+	/// and LVT entries that are not implemented according to the
+	/// [`APIC Version Register`](LocalApic::apic_version).
+	///
+	/// The [`EOI`](LocalApic::eoi) register is write-only, so it
+	/// always reads as zero.
+	pub fn read_offset(&self, offset: u16) -> Option<u32> {
+		if offset & 0xf != 0 {
+			return None;
+		}
+		let dword = match Self::decode_access(offset)? {
+			ApicAccess::Register(reg) => self.register_dword(reg)?,
+			ApicAccess::InService(i) => self.in_service[i].bitfield(),
+			ApicAccess::TriggerMode(i) => {
+				self.trigger_mode[i].bitfield()
+			}
+			ApicAccess::InterruptRequest(i) => {
+				self.interrupt_request[i].bitfield()
+			}
+		};
+		Some(dword)
+	}
+
+	/// Returns the first dword of the given register, or `None` if
+	/// it is an LVT entry that is not implemented.
+	fn register_dword(&self, reg: ApicRegisterOffset) -> Option<u32> {
+		use ApicRegisterOffset as Reg;
+		let version = self.apic_version;
+		let bytes = match reg {
+			Reg::ApicId => self.apic_id.into_bytes(),
+			Reg::Version => self.apic_version.into_bytes(),
+			Reg::TaskPriority => self.task_priority.into_bytes(),
+			Reg::ArbitrationPriority => {
+				self.arb_priority.into_bytes()
+			}
+			Reg::ProcessorPriority => {
+				self.processor_priority.into_bytes()
+			}
+			Reg::Eoi => return Some(0),
+			Reg::LogicalDestination => self.logical_dst.into_bytes(),
+			Reg::DestinationFormat => self.dst_format.into_bytes(),
+			Reg::SpuriousInterruptVector => {
+				self.spurious_iv.into_bytes()
+			}
+			Reg::InService => self.in_service[0].into_bytes(),
+			Reg::TriggerMode => self.trigger_mode[0].into_bytes(),
+			Reg::InterruptRequest => {
+				self.interrupt_request[0].into_bytes()
+			}
+			Reg::ErrorStatus => self.error_status.into_bytes(),
+			Reg::CmciLvt if version.has_cmci_lvt() => {
+				self.cmci_lvt.into_bytes()
+			}
+			Reg::InterruptCommandLow => {
+				self.interrupt_cmd_low.into_bytes()
+			}
+			Reg::InterruptCommandHigh => {
+				self.interrupt_cmd_high.into_bytes()
+			}
+			Reg::TimerLvt => self.timer_lvt.into_bytes(),
+			Reg::ThermalLvt if version.has_thermal_lvt() => {
+				self.thermal_lvt.into_bytes()
+			}
+			Reg::PerformanceLvt if version.has_perf_lvt() => {
+				self.performance_lvt.into_bytes()
+			}
+			Reg::Lint0Lvt => self.lint0_lvt.into_bytes(),
+			Reg::Lint1Lvt => self.lint1_lvt.into_bytes(),
+			Reg::ErrorLvt => self.error_lvt.into_bytes(),
+			Reg::TimerInitialCount => self.timer_icr.into_bytes(),
+			Reg::TimerCurrentCount => self.timer_ccr.into_bytes(),
+			Reg::TimerDivideConfig => self.timer_dcr.into_bytes(),
+			Reg::CmciLvt | Reg::ThermalLvt | Reg::PerformanceLvt => {
+				return None
+			}
+		};
+		Some(bytes_to_dword(bytes))
+	}
+
+	/// Reads the dword at offset `offset` of the register page. This
+	/// is the counterpart of [`apply_write`](LocalApic::apply_write)
+	/// and behaves like [`read_offset`](LocalApic::read_offset),
+	/// returning an error for illegal accesses instead of `None`.
+	/// In particular, the write-only [`EOI`](LocalApic::eoi)
+	/// register always reads as zero.
+	///
+	/// ```
+	/// # use lapic::{InvalidOffset, LocalApic};
+	/// let apic = LocalApic::default();
+	/// assert_eq!(apic.read_register(0x20), Ok(0));
+	/// assert_eq!(apic.read_register(0x40), Err(InvalidOffset(0x40)));
+	/// ```
+	#[inline]
+	pub fn read_register(
+		&self,
+		offset: u16,
+	) -> Result<u32, InvalidOffset> {
+		self.read_offset(offset).ok_or(InvalidOffset(offset))
+	}
+
+	/// Writes `value` to the first dword of the register at offset
+	/// `offset` of the register page, as a stream of writes to an
+	/// emulated local APIC would. The rest of the bits of the
+	/// register slot are left intact.
+	///
+	/// All registers can be written, including those that are
+	/// read-only on hardware, so that a full state can be rebuilt
+	/// from a sequence of writes. Returns an error for offsets in
+	/// reserved regions and offsets that are not the first dword of
+	/// a register.
+	///
+	/// ```
+	/// # use lapic::LocalApic;
+	/// let mut apic = LocalApic::default();
+	/// apic.apply_write(0x320, 0x40)?;
+	/// assert_eq!(apic.timer_lvt.vector(), 0x40);
+	/// # Ok::<(), lapic::InvalidOffset>(())
+	/// ```
+	pub fn apply_write(
+		&mut self,
+		offset: u16,
+		value: u32,
+	) -> Result<(), InvalidOffset> {
+		if offset & 0xf != 0 {
+			return Err(InvalidOffset(offset));
+		}
+		let access = Self::decode_access(offset)
+			.ok_or(InvalidOffset(offset))?;
+		match access {
+			ApicAccess::Register(reg) => {
+				self.write_register(reg, value)
+			}
+			ApicAccess::InService(i) => {
+				self.in_service[i].set_bitfield(value)
+			}
+			ApicAccess::TriggerMode(i) => {
+				self.trigger_mode[i].set_bitfield(value)
+			}
+			ApicAccess::InterruptRequest(i) => {
+				self.interrupt_request[i].set_bitfield(value)
+			}
+		}
+		Ok(())
+	}
+
+	/// Writes the first dword of the given register, which must not
+	/// be one of the bitmap registers.
+	fn write_register(&mut self, reg: ApicRegisterOffset, val: u32) {
+		use ApicRegisterOffset as Reg;
+		macro_rules! set {
+			($field:ident: $ty:ident) => {
+				self.$field
+					.overlay($ty::from_u32(val), u32::MAX.into())
+			};
+		}
+		match reg {
+			Reg::ApicId => set!(apic_id: ApicId),
+			Reg::Version => set!(apic_version: ApicVersion),
+			Reg::TaskPriority => {
+				set!(task_priority: PriorityRegister)
+			}
+			Reg::ArbitrationPriority => {
+				set!(arb_priority: PriorityRegister)
+			}
+			Reg::ProcessorPriority => {
+				set!(processor_priority: PriorityRegister)
+			}
+			Reg::Eoi => set!(eoi: EndOfInterrupt),
+			Reg::LogicalDestination => {
+				set!(logical_dst: LogicalDestination)
+			}
+			Reg::DestinationFormat => {
+				set!(dst_format: DestinationFormat)
+			}
+			Reg::SpuriousInterruptVector => {
+				set!(spurious_iv: SpuriousInterruptVector)
+			}
+			Reg::InService
+			| Reg::TriggerMode
+			| Reg::InterruptRequest => {
+				unreachable!("bitmap registers are decoded by dword")
+			}
+			Reg::ErrorStatus => set!(error_status: ErrorStatus),
+			Reg::CmciLvt => set!(cmci_lvt: CmciLVT),
+			Reg::InterruptCommandLow => {
+				set!(interrupt_cmd_low: InterruptCmdLow)
+			}
+			Reg::InterruptCommandHigh => {
+				set!(interrupt_cmd_high: InterruptCmdHigh)
+			}
+			Reg::TimerLvt => set!(timer_lvt: TimerLVT),
+			Reg::ThermalLvt => set!(thermal_lvt: ThermalLVT),
+			Reg::PerformanceLvt => set!(performance_lvt: PerfLVT),
+			Reg::Lint0Lvt => set!(lint0_lvt: LIntLVT),
+			Reg::Lint1Lvt => set!(lint1_lvt: LIntLVT),
+			Reg::ErrorLvt => set!(error_lvt: ErrorLVT),
+			Reg::TimerInitialCount => set!(timer_icr: TimerCount),
+			Reg::TimerCurrentCount => set!(timer_ccr: TimerCount),
+			Reg::TimerDivideConfig => set!(timer_dcr: TimerDivConf),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn register_offsets() {
+		macro_rules! check {
+			($($ty:ident => $field:ident,)+) => {
+				$(assert_eq!(
+					usize::from($ty::OFFSET),
+					mem::offset_of!(LocalApic, $field)
+				);)+
+			};
+		}
+		check! {
+			ApicId => apic_id,
+			ApicVersion => apic_version,
+			EndOfInterrupt => eoi,
+			LogicalDestination => logical_dst,
+			DestinationFormat => dst_format,
+			SpuriousInterruptVector => spurious_iv,
+			ErrorStatus => error_status,
+			CmciLVT => cmci_lvt,
+			InterruptCmdLow => interrupt_cmd_low,
+			InterruptCmdHigh => interrupt_cmd_high,
+			TimerLVT => timer_lvt,
+			ThermalLVT => thermal_lvt,
+			PerfLVT => performance_lvt,
+			ErrorLVT => error_lvt,
+			TimerDivConf => timer_dcr,
+		}
+	}
+
+	#[test]
+	fn apply_write() {
+		let mut apic = LocalApic::default();
+		apic.eoi.set_eoi(0xff);
+		assert_eq!(apic.apply_write(0xb0, 0), Ok(()));
+		assert_eq!(apic.eoi, EndOfInterrupt::default());
+
+		assert_eq!(apic.apply_write(0x120, 0x8000_0001), Ok(()));
+		assert_eq!(apic.in_service[2].bitfield(), 0x8000_0001);
+		assert!(apic.in_service.is_vector_set(0x5f));
+		assert_eq!(apic.read_offset(0x120), Some(0x8000_0001));
+
+		assert_eq!(
+			apic.apply_write(0x40, 1),
+			Err(InvalidOffset(0x40))
+		);
+		assert_eq!(
+			apic.apply_write(0xb4, 1),
+			Err(InvalidOffset(0xb4))
+		);
+		assert_eq!(apic.eoi, EndOfInterrupt::default());
+	}
+
+	#[test]
+	fn from_offset() {
+		assert_eq!(
+			ApicRegisterOffset::from_offset(0x380),
+			Some(ApicRegisterOffset::TimerInitialCount)
+		);
+		assert_eq!(
+			ApicRegisterOffset::from_offset(0xb4),
+			Some(ApicRegisterOffset::Eoi)
+		);
+		assert_eq!(
+			ApicRegisterOffset::from_offset(0x130),
+			Some(ApicRegisterOffset::InService)
+		);
+		assert_eq!(ApicRegisterOffset::from_offset(0xc0), None);
+		assert_eq!(ApicRegisterOffset::from_offset(0x400), None);
+		assert_eq!(ApicRegisterOffset::TimerLvt.offset(), 0x320);
+	}
+
+	#[test]
+	fn decode_access() {
+		assert_eq!(
+			LocalApic::decode_access(0x130),
+			Some(ApicAccess::InService(3))
+		);
+		assert_eq!(
+			LocalApic::decode_access(0x100),
+			Some(ApicAccess::InService(0))
+		);
+		assert_eq!(
+			LocalApic::decode_access(0x27c),
+			Some(ApicAccess::InterruptRequest(7))
+		);
+		assert_eq!(
+			LocalApic::decode_access(0xb0),
+			Some(ApicAccess::Register(ApicRegisterOffset::Eoi))
+		);
+		assert_eq!(LocalApic::decode_access(0x3f0), None);
+	}
+
+	#[test]
+	fn read_offset() {
+		let mut apic = LocalApic::default();
+		apic.timer_icr.set_count(1000);
+		apic.in_service[3] = BitfieldRegister::from_u32(0xdead);
+		assert_eq!(apic.read_offset(0x40), None);
+		assert_eq!(apic.read_offset(0x384), None);
+		assert_eq!(apic.read_offset(0x380), Some(1000));
+		assert_eq!(apic.read_offset(0x130), Some(0xdead));
+
+		// The CMCI LVT is only implemented with 7 LVT entries.
+		apic.cmci_lvt.set_vector(0x30);
+		assert_eq!(apic.read_offset(0x2f0), None);
+		apic.apic_version.set_max_lvt(6);
+		assert_eq!(apic.read_offset(0x2f0), Some(0x30));
+	}
+
+	#[test]
+	fn read_register() {
+		let mut apic = LocalApic::default();
+		apic.apic_id.set_apic_id(5);
+		apic.eoi.set_eoi(0xff);
+		assert_eq!(apic.read_register(0x20), Ok(5 << 24));
+		assert_eq!(apic.read_register(0xb0), Ok(0));
+		assert_eq!(
+			apic.read_register(0x40),
+			Err(InvalidOffset(0x40))
+		);
+		assert_eq!(
+			apic.read_register(0x24),
+			Err(InvalidOffset(0x24))
+		);
+	}
+}
@@ -0,0 +1,115 @@
+//! Helpers for starting application processors (APs).
+//!
+//! On x86 systems, the bootstrap processor (BSP) brings up each AP
+//! by sending it a fixed sequence of IPIs, usually known as
+//! INIT-SIPI-SIPI:
+//!
+//! 1. Send an INIT IPI with [`LocalApic::send_init`], and wait
+//!    10 milliseconds.
+//! 2. Send a Startup IPI (SIPI) with [`LocalApic::send_startup`],
+//!    and wait 200 microseconds.
+//! 3. If the AP has not started running, send a second SIPI with
+//!    the same vector, and wait another 200 microseconds.
+//!
+//! The vector of a SIPI is the page number of the code the AP
+//! starts executing in real mode, which must be located below 1 MiB.
+//! For example, vector `0x08` starts the AP at physical address
+//! `0x8000`.
+//!
+//! These helpers only construct and write the `ICR` values; the
+//! caller is responsible for the delays between each step, and may
+//! poll the delivery status of the `ICR` to know when each IPI has
+//! been sent.
+//!
+//! ```
+//! # use lapic::LocalApic;
+//! # fn delay_us(_: u64) {}
+//! # let mut apic = LocalApic::default();
+//! let ap_id = 1;
+//! apic.send_init(ap_id);
+//! delay_us(10_000);
+//! for _ in 0..2 {
+//!     apic.send_startup(ap_id, 0x08);
+//!     delay_us(200);
+//! }
+//! ```
+
+use crate::*;
+
+impl InterruptCmdLow {
+	/// Builds the `ICR` value for an INIT IPI: an edge-triggered,
+	/// asserted INIT to a physical destination.
+	pub(crate) fn init() -> Self {
+		Self::ipi(
+			0,
+			DeliveryMode::Init,
+			DestinationShorthand::NoShorthand,
+		)
+	}
+
+	/// Builds the `ICR` value for a Startup IPI, where `vector` is
+	/// the page number of the startup code.
+	pub(crate) fn startup(vector: u8) -> Self {
+		Self::ipi(
+			vector,
+			DeliveryMode::Startup,
+			DestinationShorthand::NoShorthand,
+		)
+	}
+}
+
+impl LocalApic {
+	/// Sends an INIT IPI to the processor with the given APIC ID,
+	/// which resets it into its wait-for-SIPI state. See the
+	/// [`smp`] module for the full startup sequence.
+	pub fn send_init(&mut self, apic_id: u8) {
+		self.interrupt_cmd_high =
+			InterruptCmdHigh::new().with_dst(apic_id);
+		self.interrupt_cmd_low = InterruptCmdLow::init();
+	}
+
+	/// Sends a Startup IPI (SIPI) to the processor with the given
+	/// APIC ID. `vector` is the page number of the code the processor
+	/// starts executing at, i.e. its physical address shifted right
+	/// by 12. See the [`smp`] module for the full
+	/// startup sequence.
+	pub fn send_startup(&mut self, apic_id: u8, vector: u8) {
+		self.interrupt_cmd_high =
+			InterruptCmdHigh::new().with_dst(apic_id);
+		self.interrupt_cmd_low = InterruptCmdLow::startup(vector);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn send_init() {
+		let mut apic = LocalApic::default();
+		apic.send_init(3);
+		let icr = apic.interrupt_cmd_low;
+		assert_eq!(apic.interrupt_cmd_high.dst(), 3);
+		assert_eq!(icr.delivery_mode(), DeliveryMode::Init);
+		assert_eq!(icr.destination_mode(), DestinationMode::Physical);
+		assert_eq!(icr.level(), Level::Assert);
+		assert_eq!(icr.trigger(), TriggerMode::Edge);
+		assert_eq!(
+			icr.shorthand(),
+			DestinationShorthand::NoShorthand
+		);
+		assert_eq!(bytes_to_dword(icr.into_bytes()), 0x4500);
+	}
+
+	#[test]
+	fn send_startup() {
+		let mut apic = LocalApic::default();
+		apic.send_startup(3, 0x08);
+		let icr = apic.interrupt_cmd_low;
+		assert_eq!(apic.interrupt_cmd_high.dst(), 3);
+		assert_eq!(icr.vector(), 0x08);
+		assert_eq!(icr.delivery_mode(), DeliveryMode::Startup);
+		assert_eq!(icr.level(), Level::Assert);
+		assert_eq!(bytes_to_dword(icr.into_bytes()), 0x4608);
+	}
+}
@@ -0,0 +1,164 @@
+//! Read-only view over the local APIC registers.
+
+use crate::*;
+
+/// Generates a getter on [`LocalApicView`] returning a copy of a
+/// register in [`LocalApic`].
+macro_rules! view_getters {
+	($($(#[$attr:meta])* $field:ident: $ty:ty;)+) => {
+		$(
+			$(#[$attr])*
+			#[inline]
+			pub fn $field(&self) -> $ty {
+				self.apic.$field
+			}
+		)+
+	};
+}
+
+/// A read-only view over a [`LocalApic`].
+///
+/// The view only exposes getters returning copies of each register,
+/// so code that should only observe the state of the APIC cannot
+/// accidentally modify it.
+///
+/// ```
+/// # use lapic::{LocalApic, LocalApicView};
+/// let mut apic = LocalApic::default();
+/// apic.timer_lvt.set_vector(0x40);
+/// let view = LocalApicView::new(&apic);
+/// assert_eq!(view.timer_lvt().vector(), 0x40);
+/// ```
+///
+/// The view does not provide any way of writing to the registers:
+///
+/// ```compile_fail
+/// # use lapic::{LocalApic, LocalApicView};
+/// let apic = LocalApic::default();
+/// let view = LocalApicView::new(&apic);
+/// view.signal_eoi();
+/// ```
+///
+/// ```compile_fail
+/// # use lapic::{LocalApic, LocalApicView};
+/// let apic = LocalApic::default();
+/// let view = LocalApicView::new(&apic);
+/// view.apic.timer_lvt.set_vector(0x40);
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct LocalApicView<'a> {
+	apic: &'a LocalApic,
+}
+
+impl<'a> LocalApicView<'a> {
+	/// Creates a new read-only view over `apic`.
+	#[inline]
+	pub const fn new(apic: &'a LocalApic) -> Self {
+		Self { apic }
+	}
+
+	view_getters! {
+		/// Returns the APIC ID Register.
+		apic_id: ApicId;
+		/// Returns the APIC Version Register.
+		apic_version: ApicVersion;
+		/// Returns the Task Priority Register (TPR).
+		task_priority: PriorityRegister;
+		/// Returns the Arbitration Priority Register (APR).
+		arb_priority: PriorityRegister;
+		/// Returns the Processor Priority Register (PPR).
+		processor_priority: PriorityRegister;
+		/// Returns the Logical Destination Register.
+		logical_dst: LogicalDestination;
+		/// Returns the Destination Format Register.
+		dst_format: DestinationFormat;
+		/// Returns the Spurious Interrupt Vector Register.
+		spurious_iv: SpuriousInterruptVector;
+		/// Returns the In-Service Register (ISR).
+		in_service: Bitmap256;
+		/// Returns the Trigger Mode Register (TMR).
+		trigger_mode: Bitmap256;
+		/// Returns the Interrupt Request Register (IRR).
+		interrupt_request: Bitmap256;
+		/// Returns the Error Status Register (ESR).
+		error_status: ErrorStatus;
+		/// Returns the CMCI Local Vector Table Entry.
+		cmci_lvt: CmciLVT;
+		/// Returns the Interrupt Command Register Low (bits 31:0).
+		interrupt_cmd_low: InterruptCmdLow;
+		/// Returns the Interrupt Command Register High (bits 63:32).
+		interrupt_cmd_high: InterruptCmdHigh;
+		/// Returns the Timer Local Vector Table Entry.
+		timer_lvt: TimerLVT;
+		/// Returns the Thermal Local Vector Table Entry.
+		thermal_lvt: ThermalLVT;
+		/// Returns the Performance Counter Local Vector Table Entry.
+		performance_lvt: PerfLVT;
+		/// Returns the Local Interrupt 0 Vector Table Entry.
+		lint0_lvt: LIntLVT;
+		/// Returns the Local Interrupt 1 Vector Table Entry.
+		lint1_lvt: LIntLVT;
+		/// Returns the Error Vector Table Entry.
+		error_lvt: ErrorLVT;
+		/// Returns the Timer Initial Count Register.
+		timer_icr: TimerCount;
+		/// Returns the Timer Current Count Register.
+		timer_ccr: TimerCount;
+		/// Returns the Timer Divide Configuration Register.
+		timer_dcr: TimerDivConf;
+	}
+
+	/// Returns the full 64-bit value of the Interrupt Command
+	/// Register. See [`LocalApic::read_icr`].
+	#[inline]
+	pub fn read_icr(&self) -> u64 {
+		self.apic.read_icr()
+	}
+
+	/// Returns an iterator over all the Local Vector Table entries.
+	/// See [`LocalApic::lvt_entries`].
+	#[inline]
+	pub fn lvt_entries(
+		&self,
+	) -> impl Iterator<Item = (LvtKind, &'a dyn MaskableLvt)> {
+		self.apic.lvt_entries()
+	}
+}
+
+impl<'a> From<&'a LocalApic> for LocalApicView<'a> {
+	#[inline]
+	fn from(apic: &'a LocalApic) -> Self {
+		Self::new(apic)
+	}
+}
+
+impl LocalApic {
+	/// Returns a read-only view over the registers.
+	#[inline]
+	pub const fn view(&self) -> LocalApicView<'_> {
+		LocalApicView::new(self)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn getters() {
+		let mut apic = LocalApic::default();
+		apic.timer_lvt.set_vector(0x40);
+		apic.processor_priority.set_priority(0x20);
+		apic.in_service[1] = BitfieldRegister::from_u32(1);
+		let view = apic.view();
+		assert_eq!(view.timer_lvt().vector(), 0x40);
+		assert_eq!(view.processor_priority().priority(), 0x20);
+		assert!(view.in_service().is_vector_set(32));
+		assert_eq!(view.lvt_entries().count(), 7);
+
+		// Getters return copies, so the viewed registers are left
+		// untouched.
+		view.timer_lvt().set_vector(0x50);
+		assert_eq!(apic.timer_lvt.vector(), 0x40);
+	}
+}
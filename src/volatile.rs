@@ -0,0 +1,326 @@
+//! Volatile accessors for a memory-mapped [`LocalApic`].
+#![allow(unsafe_code)]
+
+use crate::*;
+use core::ptr;
+
+/// Generates a volatile read accessor for a register in
+/// [`LocalApic`]. Only the first dword of the register slot is
+/// accessed, as required by the hardware.
+macro_rules! volatile_read {
+	($($(#[$attr:meta])* $field:ident: $ty:ty => $read:ident;)+) => {
+		$(
+			$(#[$attr])*
+			#[inline]
+			pub fn $read(&self) -> $ty {
+				// SAFETY: the constructor guarantees that the pointer
+				// is valid, aligned and mapped.
+				let val = unsafe {
+					let reg = ptr::addr_of!((*self.ptr).$field);
+					ptr::read_volatile(reg.cast::<u32>())
+				};
+				<$ty>::from_u32(val)
+			}
+		)+
+	};
+}
+
+/// Generates a volatile write accessor for a register in
+/// [`LocalApic`]. Only the first dword of the register slot is
+/// accessed, as required by the hardware.
+macro_rules! volatile_write {
+	($($(#[$attr:meta])* $field:ident: $ty:ty => $write:ident;)+) => {
+		$(
+			$(#[$attr])*
+			#[inline]
+			pub fn $write(&self, val: $ty) {
+				let val = bytes_to_dword(val.into_bytes());
+				// SAFETY: the constructor guarantees that the pointer
+				// is valid, aligned and mapped.
+				unsafe {
+					let reg = ptr::addr_of_mut!((*self.ptr).$field);
+					ptr::write_volatile(reg.cast::<u32>(), val);
+				}
+			}
+		)+
+	};
+}
+
+/// Generates volatile accessors for one of the bitmap registers in
+/// [`LocalApic`], indexed by their dword.
+macro_rules! volatile_read_bitmap {
+	($($(#[$attr:meta])* $field:ident => $read:ident;)+) => {
+		$(
+			$(#[$attr])*
+			///
+			/// # Panics
+			///
+			/// If `index` is not lower than 8.
+			#[inline]
+			pub fn $read(&self, index: usize) -> BitfieldRegister {
+				assert!(index < 8, "bitmap register index out of bounds");
+				// SAFETY: the constructor guarantees that the pointer
+				// is valid, aligned and mapped, and the index was
+				// checked above.
+				let val = unsafe {
+					let reg = ptr::addr_of!((*self.ptr).$field)
+						.cast::<BitfieldRegister>()
+						.add(index);
+					ptr::read_volatile(reg.cast::<u32>())
+				};
+				BitfieldRegister::from_u32(val)
+			}
+		)+
+	};
+}
+
+/// A wrapper over a pointer to a memory-mapped [`LocalApic`] that
+/// performs volatile accesses on its registers.
+///
+/// Accessing device memory through a regular reference lets the
+/// compiler reorder, merge or elide reads and writes. All the
+/// accessors in this type instead perform a single volatile access
+/// to the first dword of the corresponding register, which is where
+/// all the meaningful bits are located.
+#[derive(Copy, Clone, Debug)]
+pub struct VolatileApic {
+	ptr: *mut LocalApic,
+}
+
+impl VolatileApic {
+	/// Creates a new wrapper over the local APIC registers at `ptr`.
+	///
+	/// # Safety
+	///
+	/// The caller must guarantee that:
+	///
+	/// * `ptr` is non-null and aligned to 16 bytes, which is the
+	///   alignment of [`LocalApic`].
+	/// * `ptr` points to the local APIC register page, mapped as
+	///   uncacheable device memory (typically at physical address
+	///   `0xFEE00000`).
+	/// * The mapping remains valid for reads and writes for as long
+	///   as the returned value (or any copy of it) is in use.
+	#[inline]
+	pub const unsafe fn new(ptr: *mut LocalApic) -> Self {
+		Self { ptr }
+	}
+
+	/// Returns the pointer to the registers wrapped by this type.
+	#[inline]
+	pub const fn as_ptr(&self) -> *mut LocalApic {
+		self.ptr
+	}
+
+	/// Signals the end of the current interrupt by writing zero to
+	/// the [`EOI`](LocalApic::eoi) register. This is the volatile
+	/// equivalent of [`LocalApic::signal_eoi`].
+	#[inline]
+	pub fn signal_eoi(&self) {
+		self.write_eoi(EndOfInterrupt::default());
+	}
+
+	/// Sends an Inter-Processor Interrupt (IPI). This is the
+	/// volatile equivalent of [`LocalApic::send_ipi`], and
+	/// guarantees that the
+	/// [`ICR` high register](LocalApic::interrupt_cmd_high) is
+	/// written before the
+	/// [`ICR` low register](LocalApic::interrupt_cmd_low), which
+	/// triggers the delivery.
+	pub fn send_ipi(
+		&self,
+		dst: u8,
+		vector: u8,
+		mode: DeliveryMode,
+		shorthand: DestinationShorthand,
+	) {
+		self.write_interrupt_cmd_high(
+			InterruptCmdHigh::new().with_dst(dst),
+		);
+		self.write_interrupt_cmd_low(InterruptCmdLow::ipi(
+			vector, mode, shorthand,
+		));
+	}
+
+	/// Reads the Error Status Register (ESR).
+	///
+	/// The ESR is only updated with the errors detected since its
+	/// last update when it is written to, so a single read returns
+	/// stale data. This method performs the required write of zero
+	/// before reading the register, which also clears the errors
+	/// accumulated in the hardware.
+	pub fn read_error_status(&self) -> ErrorStatus {
+		self.write_error_status(ErrorStatus::new());
+		// SAFETY: the constructor guarantees that the pointer is
+		// valid, aligned and mapped.
+		let val = unsafe {
+			let reg = ptr::addr_of!((*self.ptr).error_status);
+			ptr::read_volatile(reg.cast::<u32>())
+		};
+		ErrorStatus::from_u32(val)
+	}
+
+	/// Busy-waits until the last IPI sent through the `ICR` has been
+	/// accepted, i.e. until [`InterruptCmdLow::is_send_pending`]
+	/// returns `false`.
+	///
+	/// This spins on volatile reads of the
+	/// [`ICR` low register](LocalApic::interrupt_cmd_low) with no
+	/// timeout, so it never returns if the hardware does not clear
+	/// the delivery status. Callers that need a bounded wait should
+	/// poll [`read_interrupt_cmd_low`](Self::read_interrupt_cmd_low)
+	/// themselves.
+	pub fn wait_for_idle(&self) {
+		while self.read_interrupt_cmd_low().is_send_pending() {
+			core::hint::spin_loop();
+		}
+	}
+
+	volatile_read! {
+		/// Reads the APIC ID Register.
+		apic_id: ApicId => read_apic_id;
+		/// Reads the APIC Version Register.
+		apic_version: ApicVersion => read_apic_version;
+		/// Reads the Task Priority Register (TPR).
+		task_priority: PriorityRegister => read_task_priority;
+		/// Reads the Arbitration Priority Register (APR).
+		arb_priority: PriorityRegister => read_arb_priority;
+		/// Reads the Processor Priority Register (PPR).
+		processor_priority: PriorityRegister => read_processor_priority;
+		/// Reads the Logical Destination Register.
+		logical_dst: LogicalDestination => read_logical_dst;
+		/// Reads the Destination Format Register.
+		dst_format: DestinationFormat => read_dst_format;
+		/// Reads the Spurious Interrupt Vector Register.
+		spurious_iv: SpuriousInterruptVector => read_spurious_iv;
+		/// Reads the CMCI Local Vector Table Entry.
+		cmci_lvt: CmciLVT => read_cmci_lvt;
+		/// Reads the Interrupt Command Register Low (bits 31:0).
+		interrupt_cmd_low: InterruptCmdLow => read_interrupt_cmd_low;
+		/// Reads the Interrupt Command Register High (bits 63:32).
+		interrupt_cmd_high: InterruptCmdHigh => read_interrupt_cmd_high;
+		/// Reads the Timer Local Vector Table Entry.
+		timer_lvt: TimerLVT => read_timer_lvt;
+		/// Reads the Thermal Local Vector Table Entry.
+		thermal_lvt: ThermalLVT => read_thermal_lvt;
+		/// Reads the Performance Counter Local Vector Table Entry.
+		performance_lvt: PerfLVT => read_performance_lvt;
+		/// Reads the Local Interrupt 0 Vector Table Entry.
+		lint0_lvt: LIntLVT => read_lint0_lvt;
+		/// Reads the Local Interrupt 1 Vector Table Entry.
+		lint1_lvt: LIntLVT => read_lint1_lvt;
+		/// Reads the Error Vector Table Entry.
+		error_lvt: ErrorLVT => read_error_lvt;
+		/// Reads the Timer Initial Count Register.
+		timer_icr: TimerCount => read_timer_icr;
+		/// Reads the Timer Current Count Register.
+		timer_ccr: TimerCount => read_timer_ccr;
+		/// Reads the Timer Divide Configuration Register.
+		timer_dcr: TimerDivConf => read_timer_dcr;
+	}
+
+	volatile_read_bitmap! {
+		/// Reads one dword of the In-Service Register (ISR).
+		in_service => read_in_service;
+		/// Reads one dword of the Trigger Mode Register (TMR).
+		trigger_mode => read_trigger_mode;
+		/// Reads one dword of the Interrupt Request Register (IRR).
+		interrupt_request => read_interrupt_request;
+	}
+
+	volatile_write! {
+		/// Writes the APIC ID Register.
+		apic_id: ApicId => write_apic_id;
+		/// Writes the Task Priority Register (TPR).
+		task_priority: PriorityRegister => write_task_priority;
+		/// Writes the End of Interrupt Register (EOI).
+		eoi: EndOfInterrupt => write_eoi;
+		/// Writes the Logical Destination Register.
+		logical_dst: LogicalDestination => write_logical_dst;
+		/// Writes the Destination Format Register.
+		dst_format: DestinationFormat => write_dst_format;
+		/// Writes the Spurious Interrupt Vector Register.
+		spurious_iv: SpuriousInterruptVector => write_spurious_iv;
+		/// Writes the Error Status Register (ESR).
+		error_status: ErrorStatus => write_error_status;
+		/// Writes the CMCI Local Vector Table Entry.
+		cmci_lvt: CmciLVT => write_cmci_lvt;
+		/// Writes the Interrupt Command Register Low (bits 31:0).
+		/// This write triggers the delivery of the interrupt.
+		interrupt_cmd_low: InterruptCmdLow => write_interrupt_cmd_low;
+		/// Writes the Interrupt Command Register High (bits 63:32).
+		interrupt_cmd_high: InterruptCmdHigh => write_interrupt_cmd_high;
+		/// Writes the Timer Local Vector Table Entry.
+		timer_lvt: TimerLVT => write_timer_lvt;
+		/// Writes the Thermal Local Vector Table Entry.
+		thermal_lvt: ThermalLVT => write_thermal_lvt;
+		/// Writes the Performance Counter Local Vector Table Entry.
+		performance_lvt: PerfLVT => write_performance_lvt;
+		/// Writes the Local Interrupt 0 Vector Table Entry.
+		lint0_lvt: LIntLVT => write_lint0_lvt;
+		/// Writes the Local Interrupt 1 Vector Table Entry.
+		lint1_lvt: LIntLVT => write_lint1_lvt;
+		/// Writes the Error Vector Table Entry.
+		error_lvt: ErrorLVT => write_error_lvt;
+		/// Writes the Timer Initial Count Register.
+		timer_icr: TimerCount => write_timer_icr;
+		/// Writes the Timer Divide Configuration Register.
+		timer_dcr: TimerDivConf => write_timer_dcr;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn read_write() {
+		let mut apic = LocalApic::default();
+		let vol = unsafe { VolatileApic::new(&mut apic) };
+
+		vol.write_timer_lvt(TimerLVT::default().with_vector(0x40));
+		assert_eq!(vol.read_timer_lvt().vector(), 0x40);
+		vol.write_timer_icr(TimerCount::default().with_count(1000));
+		assert_eq!(vol.read_timer_icr().count(), 1000);
+
+		apic.in_service[3] =
+			BitfieldRegister::default().with_bitfield(0xdead);
+		let vol = unsafe { VolatileApic::new(&mut apic) };
+		assert_eq!(vol.read_in_service(3).bitfield(), 0xdead);
+		assert_eq!(vol.read_in_service(2).bitfield(), 0);
+		assert_eq!(apic.timer_lvt.vector(), 0x40);
+	}
+
+	#[test]
+	fn wait_for_idle() {
+		let mut apic = LocalApic::default();
+		let vol = unsafe { VolatileApic::new(&mut apic) };
+		vol.send_ipi(
+			1,
+			0x40,
+			DeliveryMode::Fixed,
+			DestinationShorthand::NoShorthand,
+		);
+		vol.wait_for_idle();
+	}
+
+	#[test]
+	fn read_error_status() {
+		let mut apic = LocalApic::default();
+		apic.error_status.set_send_illegal_vector(1);
+		let vol = unsafe { VolatileApic::new(&mut apic) };
+		// In memory the dummy write is not followed by a hardware
+		// update, so the register reads back as cleared.
+		assert!(vol.read_error_status().is_clear());
+		assert!(apic.error_status.is_clear());
+	}
+
+	#[test]
+	fn signal_eoi() {
+		let mut apic = LocalApic::default();
+		apic.eoi.set_eoi(0xff);
+		let vol = unsafe { VolatileApic::new(&mut apic) };
+		vol.signal_eoi();
+		assert_eq!(apic.eoi, EndOfInterrupt::default());
+	}
+}
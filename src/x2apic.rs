@@ -0,0 +1,427 @@
+//! Support for the x2APIC mode of the local APIC.
+//!
+//! In x2APIC mode the local APIC registers are accessed through
+//! Model-Specific Registers (MSRs) in the `0x800`-`0x8FF` range
+//! instead of memory-mapped I/O. Each register is accessed as a
+//! single 32-bit value, except for the Interrupt Command Register,
+//! which is a single 64-bit MSR.
+
+use crate::*;
+
+/// MSR addresses of the local APIC registers in x2APIC mode.
+#[repr(u32)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum X2ApicRegister {
+	/// APIC ID Register.
+	ApicId = 0x802,
+	/// APIC Version Register.
+	Version = 0x803,
+	/// Task Priority Register (TPR).
+	TaskPriority = 0x808,
+	/// Processor Priority Register (PPR).
+	ProcessorPriority = 0x80a,
+	/// End of Interrupt Register (EOI).
+	Eoi = 0x80b,
+	/// Logical Destination Register.
+	LogicalDestination = 0x80d,
+	/// Spurious Interrupt Vector Register.
+	SpuriousInterruptVector = 0x80f,
+	/// First of the 8 In-Service Registers (ISR).
+	InService = 0x810,
+	/// First of the 8 Trigger Mode Registers (TMR).
+	TriggerMode = 0x818,
+	/// First of the 8 Interrupt Request Registers (IRR).
+	InterruptRequest = 0x820,
+	/// Error Status Register (ESR).
+	ErrorStatus = 0x828,
+	/// CMCI Local Vector Table Entry.
+	CmciLvt = 0x82f,
+	/// Interrupt Command Register (ICR).
+	InterruptCommand = 0x830,
+	/// Timer Local Vector Table Entry.
+	TimerLvt = 0x832,
+	/// Thermal Local Vector Table Entry.
+	ThermalLvt = 0x833,
+	/// Performance Counter Local Vector Table Entry.
+	PerformanceLvt = 0x834,
+	/// Local Interrupt 0 Vector Table Entry.
+	Lint0Lvt = 0x835,
+	/// Local Interrupt 1 Vector Table Entry.
+	Lint1Lvt = 0x836,
+	/// Error Vector Table Entry.
+	ErrorLvt = 0x837,
+	/// Timer Initial Count Register.
+	TimerInitialCount = 0x838,
+	/// Timer Current Count Register.
+	TimerCurrentCount = 0x839,
+	/// Timer Divide Configuration Register.
+	TimerDivideConfig = 0x83e,
+	/// Self IPI Register.
+	SelfIpi = 0x83f,
+}
+
+impl X2ApicRegister {
+	/// Returns the MSR address of the register.
+	#[inline]
+	pub const fn msr(self) -> u32 {
+		self as u32
+	}
+}
+
+/// Interrupt Command Register (ICR) in x2APIC mode.
+///
+/// Unlike in xAPIC mode, the ICR is a single 64-bit MSR, and the
+/// destination field is a full 32-bit x2APIC ID. It can be converted
+/// to and from the raw `u64` value of the MSR.
+///
+/// Writes to the ICR are serializing and have no pending state, so
+/// there is no delivery status bit to poll: bits 12 and 13 are
+/// reserved.
+#[bitfield(bits = 64)]
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bytemuck", derive(Pod, Zeroable))]
+#[cfg_attr(
+	feature = "zerocopy",
+	derive(FromZeroes, FromBytes, AsBytes)
+)]
+#[repr(transparent)]
+pub struct X2ApicIcr {
+	pub vector: u8,
+	#[bits = 3]
+	pub delivery_mode: DeliveryMode,
+	#[bits = 1]
+	pub destination_mode: DestinationMode,
+	#[skip]
+	__: B2,
+	#[bits = 1]
+	pub level: Level,
+	#[bits = 1]
+	pub trigger: TriggerMode,
+	#[skip]
+	__: B2,
+	#[bits = 2]
+	pub shorthand: DestinationShorthand,
+	#[skip]
+	__: B12,
+	pub dst: u32,
+}
+
+impl From<u64> for X2ApicIcr {
+	#[inline]
+	fn from(val: u64) -> Self {
+		Self::from_bytes(val.to_le_bytes())
+	}
+}
+
+impl From<X2ApicIcr> for u64 {
+	#[inline]
+	fn from(val: X2ApicIcr) -> Self {
+		u64::from_le_bytes(val.into_bytes())
+	}
+}
+
+/// Self IPI Register in x2APIC mode.
+///
+/// Writing this register sends a fixed, edge-triggered IPI with the
+/// given vector to the local processor. This register is write-only
+/// and not present in xAPIC mode.
+#[bitfield(bits = 32)]
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bytemuck", derive(Pod, Zeroable))]
+#[cfg_attr(
+	feature = "zerocopy",
+	derive(FromZeroes, FromBytes, AsBytes)
+)]
+#[repr(transparent)]
+pub struct X2ApicSelfIpi {
+	pub vector: u8,
+	#[skip]
+	__: B24,
+}
+
+impl From<u32> for X2ApicSelfIpi {
+	#[inline]
+	fn from(val: u32) -> Self {
+		Self::from_bytes(val.to_le_bytes())
+	}
+}
+
+impl From<X2ApicSelfIpi> for u32 {
+	#[inline]
+	fn from(val: X2ApicSelfIpi) -> Self {
+		u32::from_le_bytes(val.into_bytes())
+	}
+}
+
+/// Access to Model-Specific Registers (MSRs).
+///
+/// This crate does not execute any instructions by itself, so users
+/// must implement this trait, typically through the `rdmsr` and
+/// `wrmsr` instructions, to use [`X2Apic`].
+pub trait MsrAccess {
+	/// Reads the MSR at address `msr`.
+	fn read_msr(&self, msr: u32) -> u64;
+
+	/// Writes `val` to the MSR at address `msr`.
+	fn write_msr(&self, msr: u32, val: u64);
+}
+
+/// Generates accessors for a 32-bit x2APIC register.
+macro_rules! x2apic_read {
+	($($(#[$attr:meta])* $reg:ident: $ty:ty => $read:ident;)+) => {
+		$(
+			$(#[$attr])*
+			#[inline]
+			pub fn $read(&self) -> $ty {
+				let msr = X2ApicRegister::$reg.msr();
+				<$ty>::from_u32(self.msr.read_msr(msr) as u32)
+			}
+		)+
+	};
+}
+
+/// Generates accessors for a 32-bit x2APIC register.
+macro_rules! x2apic_write {
+	($($(#[$attr:meta])* $reg:ident: $ty:ty => $write:ident;)+) => {
+		$(
+			$(#[$attr])*
+			#[inline]
+			pub fn $write(&self, val: $ty) {
+				let msr = X2ApicRegister::$reg.msr();
+				let val = bytes_to_dword(val.into_bytes());
+				self.msr.write_msr(msr, val.into());
+			}
+		)+
+	};
+}
+
+/// Generates accessors for one of the x2APIC bitmap registers,
+/// indexed by their dword.
+macro_rules! x2apic_read_bitmap {
+	($($(#[$attr:meta])* $reg:ident => $read:ident;)+) => {
+		$(
+			$(#[$attr])*
+			///
+			/// # Panics
+			///
+			/// If `index` is not lower than 8.
+			#[inline]
+			pub fn $read(&self, index: usize) -> BitfieldRegister {
+				assert!(index < 8, "bitmap register index out of bounds");
+				let msr = X2ApicRegister::$reg.msr() + index as u32;
+				BitfieldRegister::from_u32(self.msr.read_msr(msr) as u32)
+			}
+		)+
+	};
+}
+
+/// The local APIC registers in x2APIC mode, accessed through the
+/// given [`MsrAccess`] implementation.
+///
+/// The register types are shared with [`LocalApic`], except for the
+/// APIC ID and Logical Destination registers, which are full 32-bit
+/// values in x2APIC mode, and the [`X2ApicIcr`].
+#[derive(Copy, Clone, Debug)]
+pub struct X2Apic<M> {
+	msr: M,
+}
+
+impl<M: MsrAccess> X2Apic<M> {
+	/// Creates a new accessor for the x2APIC registers.
+	#[inline]
+	pub const fn new(msr: M) -> Self {
+		Self { msr }
+	}
+
+	/// Returns a reference to the underlying MSR accessor.
+	#[inline]
+	pub const fn msr_access(&self) -> &M {
+		&self.msr
+	}
+
+	/// Reads the 32-bit x2APIC ID.
+	#[inline]
+	pub fn read_apic_id(&self) -> u32 {
+		self.msr.read_msr(X2ApicRegister::ApicId.msr()) as u32
+	}
+
+	/// Reads the 32-bit logical x2APIC ID.
+	#[inline]
+	pub fn read_logical_dst(&self) -> u32 {
+		let msr = X2ApicRegister::LogicalDestination.msr();
+		self.msr.read_msr(msr) as u32
+	}
+
+	/// Reads the Interrupt Command Register (ICR).
+	#[inline]
+	pub fn read_icr(&self) -> X2ApicIcr {
+		let msr = X2ApicRegister::InterruptCommand.msr();
+		X2ApicIcr::from(self.msr.read_msr(msr))
+	}
+
+	/// Writes the Interrupt Command Register (ICR), which triggers
+	/// the delivery of the interrupt.
+	#[inline]
+	pub fn write_icr(&self, val: X2ApicIcr) {
+		let msr = X2ApicRegister::InterruptCommand.msr();
+		self.msr.write_msr(msr, val.into());
+	}
+
+	/// Writes the Self IPI Register.
+	#[inline]
+	pub fn write_self_ipi(&self, val: X2ApicSelfIpi) {
+		let msr = X2ApicRegister::SelfIpi.msr();
+		self.msr.write_msr(msr, u32::from(val).into());
+	}
+
+	/// Sends a fixed IPI with the given vector to the local
+	/// processor through the Self IPI Register.
+	///
+	/// In x2APIC mode this avoids a full ICR write; in xAPIC mode
+	/// there is no Self IPI register and the ICR self shorthand
+	/// ([`LocalApic::send_self_ipi`]) must be used.
+	#[inline]
+	pub fn self_ipi(&self, vector: u8) {
+		self.write_self_ipi(X2ApicSelfIpi::new().with_vector(vector));
+	}
+
+	x2apic_read! {
+		/// Reads the APIC Version Register.
+		Version: ApicVersion => read_apic_version;
+		/// Reads the Task Priority Register (TPR).
+		TaskPriority: PriorityRegister => read_task_priority;
+		/// Reads the Processor Priority Register (PPR).
+		ProcessorPriority: PriorityRegister => read_processor_priority;
+		/// Reads the Spurious Interrupt Vector Register.
+		SpuriousInterruptVector: SpuriousInterruptVector =>
+			read_spurious_iv;
+		/// Reads the Error Status Register (ESR).
+		ErrorStatus: ErrorStatus => read_error_status;
+		/// Reads the CMCI Local Vector Table Entry.
+		CmciLvt: CmciLVT => read_cmci_lvt;
+		/// Reads the Timer Local Vector Table Entry.
+		TimerLvt: TimerLVT => read_timer_lvt;
+		/// Reads the Thermal Local Vector Table Entry.
+		ThermalLvt: ThermalLVT => read_thermal_lvt;
+		/// Reads the Performance Counter Local Vector Table Entry.
+		PerformanceLvt: PerfLVT => read_performance_lvt;
+		/// Reads the Local Interrupt 0 Vector Table Entry.
+		Lint0Lvt: LIntLVT => read_lint0_lvt;
+		/// Reads the Local Interrupt 1 Vector Table Entry.
+		Lint1Lvt: LIntLVT => read_lint1_lvt;
+		/// Reads the Error Vector Table Entry.
+		ErrorLvt: ErrorLVT => read_error_lvt;
+		/// Reads the Timer Initial Count Register.
+		TimerInitialCount: TimerCount => read_timer_icr;
+		/// Reads the Timer Current Count Register.
+		TimerCurrentCount: TimerCount => read_timer_ccr;
+		/// Reads the Timer Divide Configuration Register.
+		TimerDivideConfig: TimerDivConf => read_timer_dcr;
+	}
+
+	x2apic_read_bitmap! {
+		/// Reads one dword of the In-Service Register (ISR).
+		InService => read_in_service;
+		/// Reads one dword of the Trigger Mode Register (TMR).
+		TriggerMode => read_trigger_mode;
+		/// Reads one dword of the Interrupt Request Register (IRR).
+		InterruptRequest => read_interrupt_request;
+	}
+
+	x2apic_write! {
+		/// Writes the Task Priority Register (TPR).
+		TaskPriority: PriorityRegister => write_task_priority;
+		/// Writes the End of Interrupt Register (EOI).
+		Eoi: EndOfInterrupt => write_eoi;
+		/// Writes the Spurious Interrupt Vector Register.
+		SpuriousInterruptVector: SpuriousInterruptVector =>
+			write_spurious_iv;
+		/// Writes the Error Status Register (ESR).
+		ErrorStatus: ErrorStatus => write_error_status;
+		/// Writes the CMCI Local Vector Table Entry.
+		CmciLvt: CmciLVT => write_cmci_lvt;
+		/// Writes the Timer Local Vector Table Entry.
+		TimerLvt: TimerLVT => write_timer_lvt;
+		/// Writes the Thermal Local Vector Table Entry.
+		ThermalLvt: ThermalLVT => write_thermal_lvt;
+		/// Writes the Performance Counter Local Vector Table Entry.
+		PerformanceLvt: PerfLVT => write_performance_lvt;
+		/// Writes the Local Interrupt 0 Vector Table Entry.
+		Lint0Lvt: LIntLVT => write_lint0_lvt;
+		/// Writes the Local Interrupt 1 Vector Table Entry.
+		Lint1Lvt: LIntLVT => write_lint1_lvt;
+		/// Writes the Error Vector Table Entry.
+		ErrorLvt: ErrorLVT => write_error_lvt;
+		/// Writes the Timer Initial Count Register.
+		TimerInitialCount: TimerCount => write_timer_icr;
+		/// Writes the Timer Divide Configuration Register.
+		TimerDivideConfig: TimerDivConf => write_timer_dcr;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use core::cell::RefCell;
+
+	/// Fake MSR space for the x2APIC range.
+	struct FakeMsrs(RefCell<[u64; 0x40]>);
+
+	impl Default for FakeMsrs {
+		fn default() -> Self {
+			Self(RefCell::new([0; 0x40]))
+		}
+	}
+
+	impl MsrAccess for FakeMsrs {
+		fn read_msr(&self, msr: u32) -> u64 {
+			self.0.borrow()[(msr - 0x800) as usize]
+		}
+
+		fn write_msr(&self, msr: u32, val: u64) {
+			self.0.borrow_mut()[(msr - 0x800) as usize] = val;
+		}
+	}
+
+	#[test]
+	fn icr_layout() {
+		let icr = X2ApicIcr::new()
+			.with_vector(0x40)
+			.with_delivery_mode(DeliveryMode::Nmi)
+			.with_level(Level::Assert)
+			.with_dst(0x1234_5678);
+		assert_eq!(u64::from(icr), 0x1234_5678_0000_4440);
+		assert_eq!(X2ApicIcr::from(u64::from(icr)), icr);
+	}
+
+	#[test]
+	fn msr_accesses() {
+		let apic = X2Apic::new(FakeMsrs::default());
+		apic.write_icr(
+			X2ApicIcr::new().with_vector(0x30).with_dst(7),
+		);
+		apic.write_timer_lvt(TimerLVT::default().with_vector(0x40));
+		apic.write_eoi(EndOfInterrupt::default());
+
+		let msrs = apic.msr_access().0.borrow();
+		assert_eq!(msrs[0x30], 0x7_0000_0030);
+		assert_eq!(msrs[0x32], 0x40);
+		drop(msrs);
+
+		apic.msr_access().0.borrow_mut()[0x02] = 0xabcd;
+		apic.msr_access().0.borrow_mut()[0x13] = 1 << 5;
+		assert_eq!(apic.read_apic_id(), 0xabcd);
+		assert_eq!(apic.read_in_service(3).bitfield(), 1 << 5);
+		assert_eq!(apic.read_timer_lvt().vector(), 0x40);
+		assert_eq!(apic.read_icr().dst(), 7);
+	}
+
+	#[test]
+	fn self_ipi() {
+		let apic = X2Apic::new(FakeMsrs::default());
+		apic.self_ipi(0x55);
+		assert_eq!(apic.msr_access().0.borrow()[0x3f], 0x55);
+	}
+}